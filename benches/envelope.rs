@@ -0,0 +1,43 @@
+//! Per-sample cost of synthesizing with envelopes of increasing segment count.
+//!
+//! Run with `cargo bench --bench envelope`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rs_fxr::{envelope::Envelope, synth::Synth, waveform::Sine};
+
+const SAMPLE_RATE: u32 = 44100;
+const DURATION: f64 = 2.;
+
+fn envelope(segments: usize, low: f64, high: f64) -> Envelope {
+    let points = (0..=segments)
+        .map(|i| {
+            let t = DURATION * i as f64 / segments as f64;
+            (t, if i % 2 == 0 { low } else { high })
+        })
+        .collect();
+    Envelope::from_points(points, None).unwrap()
+}
+
+fn main() {
+    for segments in [2, 16, 128, 1024, 8192, 65536] {
+        let freq = envelope(segments, 220., 880.);
+        let amp = envelope(segments, 0.1, 1.);
+        let synth = Synth::new(SAMPLE_RATE, Sine::new(freq), amp).unwrap();
+
+        let start = Instant::now();
+        let mut count = 0usize;
+        for sample in synth {
+            black_box(sample);
+            count += 1;
+        }
+        let elapsed = start.elapsed();
+
+        println!(
+            "{:>6} segments: {:>8.1} ns/sample",
+            segments,
+            elapsed.as_nanos() as f64 / count as f64
+        );
+    }
+}
@@ -1,5 +1,8 @@
-use crate::traits::{Duration, Proc, Synth};
+use crate::convert::{Converter, Dither, Rounding};
+use crate::effect::{downsample::Downsample, EffectChain};
+use crate::traits::{Duration, SampleRate, Synth};
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BitCrush {
     B1,
     B2,
@@ -62,11 +65,74 @@ impl BitCrush {
             BitCrush::B16 => unimplemented!(),
         }
     }
+
+    pub fn bits(&self) -> u32 {
+        *self as u32 + 1
+    }
+
+    /// Keeps the `bits` most significant bits of the magnitude, like [`BitCrush::crush_i16`].
+    pub fn crush(&self, sample: f64) -> f64 {
+        if let BitCrush::B16 = self {
+            return sample.clamp(-1., 1.);
+        }
+        let scale = i16::MAX as f64;
+        let step = (1 << (15 - self.bits())) as f64;
+        let sample = sample.clamp(-1., 1.);
+        sample.signum() * (sample.abs() * scale / step).floor() * step / scale
+    }
+
+    pub fn crush_i16(&self, sample: i16) -> i16 {
+        let sample = sample.max(-i16::MAX);
+        if let BitCrush::B16 = self {
+            sample
+        } else if sample > 0 {
+            sample & self.mask()
+        } else if sample < 0 {
+            -(-sample & self.mask())
+        } else {
+            0
+        }
+    }
 }
 
 pub struct BitCrushedSound<'a> {
     sound: Box<dyn Synth + 'a>,
     bit_mask: BitCrush,
+    converter: Converter,
+}
+
+impl<'a> BitCrushedSound<'a> {
+    /// Rounding and dither applied at 16-bit resolution before crushing.
+    pub fn with_converter(self, converter: Converter) -> Self {
+        Self { converter, ..self }
+    }
+
+    /// Holds every input sample for `factor` output samples, see [`Downsample`].
+    pub fn with_downsample(self, factor: usize) -> Self {
+        let sample_rate = self.sound.sample_rate();
+        let target_rate = sample_rate as f64 / factor.max(1) as f64;
+        let downsample = Downsample::new_simple(sample_rate, target_rate).unwrap();
+        Self {
+            sound: Box::new(self.sound.effect(downsample)),
+            ..self
+        }
+    }
+
+    /// Renders with the original integer bit masking instead of float quantization.
+    pub fn render_16<T>(mut self) -> crate::Samples<T>
+    where
+        T: From<i16>,
+    {
+        let mut samples = vec![];
+        for sample in self.sound.by_ref() {
+            let sample = self.converter.convert::<i16>(sample);
+            samples.push(self.bit_mask.crush_i16(sample).into());
+        }
+        crate::Samples::<T> {
+            sample_rate: self.sound.sample_rate(),
+            samples,
+        }
+    }
 }
 
 unsafe impl<'a> Send for BitCrushedSound<'a> {}
@@ -77,26 +143,21 @@ impl<'a> Duration for BitCrushedSound<'a> {
     }
 }
 
+impl<'a> SampleRate for BitCrushedSound<'a> {
+    fn sample_rate(&self) -> u32 {
+        self.sound.sample_rate()
+    }
+}
+
+impl<'a> Synth for BitCrushedSound<'a> {}
+
 impl<'a> Iterator for BitCrushedSound<'a> {
-    type Item = i16;
+    type Item = f64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(sample) = self.sound.next() {
-            let sample = (f64::clamp(sample, -1., 1.) * i16::MAX as f64) as i16;
-            if let BitCrush::B16 = self.bit_mask {
-                Some(sample)
-            } else {
-                Some(if sample > 0 {
-                    sample & self.bit_mask.mask()
-                } else if sample < 0 {
-                    -(-sample & self.bit_mask.mask())
-                } else {
-                    0
-                })
-            }
-        } else {
-            None
-        }
+        let sample = self.sound.next()?;
+        let sample = self.converter.quantize(sample, i16::MAX as f64);
+        Some(self.bit_mask.crush(sample))
     }
 }
 
@@ -104,15 +165,15 @@ pub trait BitCrushable<'a> {
     fn bit_crush(self, bit_mask: BitCrush) -> BitCrushedSound<'a>;
 }
 
-impl<'a, W, E> BitCrushable<'a> for crate::synth::Synth<W, E>
+impl<'a, S> BitCrushable<'a> for S
 where
-    W: Proc + 'a,
-    E: Proc + Duration + 'a,
+    S: Synth + 'a,
 {
     fn bit_crush(self, bit_mask: BitCrush) -> BitCrushedSound<'a> {
         BitCrushedSound {
             sound: Box::new(self),
             bit_mask,
+            converter: Converter::new(Rounding::TowardZero, Dither::None),
         }
     }
 }
@@ -0,0 +1,111 @@
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+struct ParamState {
+    target: AtomicU64,
+    current: AtomicU64,
+    t: AtomicU64,
+}
+
+/// A value that can be set from any thread while a sound plays, and which glides
+/// towards each new setting to avoid zipper noise.
+#[derive(Clone)]
+pub struct Param {
+    state: Arc<ParamState>,
+    smoothing: f64,
+}
+
+impl Param {
+    /// `smoothing` is the time constant of the glide, in seconds; zero jumps immediately.
+    pub fn new(value: f64, smoothing: f64) -> Self {
+        Self {
+            state: Arc::new(ParamState {
+                target: AtomicU64::new(value.to_bits()),
+                current: AtomicU64::new(value.to_bits()),
+                t: AtomicU64::new(0f64.to_bits()),
+            }),
+            smoothing: smoothing.max(0.),
+        }
+    }
+
+    pub fn set(&self, value: f64) {
+        if !value.is_nan() {
+            self.state.target.store(value.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    pub fn target(&self) -> f64 {
+        f64::from_bits(self.state.target.load(Ordering::Relaxed))
+    }
+
+    /// Smoothed value at `t`, advancing the glide; `t` must not decrease between calls.
+    pub(crate) fn value(&self, t: f64) -> f64 {
+        let current = f64::from_bits(self.state.current.load(Ordering::Relaxed));
+        let dt = t - f64::from_bits(self.state.t.load(Ordering::Relaxed));
+        if dt <= 0. {
+            return current;
+        }
+        let target = self.target();
+        let value = if self.smoothing > 0. {
+            target + (current - target) * (-dt / self.smoothing).exp()
+        } else {
+            target
+        };
+        self.state.current.store(value.to_bits(), Ordering::Relaxed);
+        self.state.t.store(t.to_bits(), Ordering::Relaxed);
+        value
+    }
+}
+
+/// Handle to the frequency, amplitude and filter cutoff of a playing sound, see
+/// [`crate::serde::Description::build_controlled`].
+#[derive(Clone)]
+pub struct Control {
+    base_frequency: f64,
+    pub(crate) frequency: Param,
+    pub(crate) amplitude: Param,
+    pub(crate) cutoff: Param,
+}
+
+impl Control {
+    pub const SMOOTHING: f64 = 0.01;
+
+    /// Starts at `frequency` in Hz, unit amplitude and `cutoff` in Hz.
+    pub fn new(frequency: f64, cutoff: f64) -> Self {
+        Self {
+            base_frequency: frequency,
+            frequency: Param::new(1., Self::SMOOTHING),
+            amplitude: Param::new(1., Self::SMOOTHING),
+            cutoff: Param::new(cutoff, Self::SMOOTHING),
+        }
+    }
+
+    /// Moves the sound's base frequency to `frequency` Hz, keeping its sweeps relative.
+    pub fn set_frequency(&self, frequency: f64) {
+        self.frequency.set(frequency / self.base_frequency);
+    }
+
+    /// Scales the amplitude envelope by `gain`.
+    pub fn set_amplitude(&self, gain: f64) {
+        self.amplitude.set(gain);
+    }
+
+    /// Sets the low-pass cutoff in Hz.
+    pub fn set_cutoff(&self, cutoff: f64) {
+        self.cutoff.set(cutoff);
+    }
+
+    pub fn frequency(&self) -> f64 {
+        self.frequency.target() * self.base_frequency
+    }
+
+    pub fn amplitude(&self) -> f64 {
+        self.amplitude.target()
+    }
+
+    pub fn cutoff(&self) -> f64 {
+        self.cutoff.target()
+    }
+}
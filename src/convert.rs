@@ -0,0 +1,197 @@
+use rand::{distributions::Uniform, rngs::StdRng, Rng, SeedableRng};
+
+pub trait SampleFormat: Copy {
+    /// Largest positive integer value, or `1.` for floating point formats.
+    const FULL_SCALE: f64;
+    const INTEGER: bool;
+
+    /// Builds a sample from a value already clamped to the format's range
+    /// (and rounded, for integer formats).
+    fn from_f64(value: f64) -> Self;
+}
+
+impl SampleFormat for i8 {
+    const FULL_SCALE: f64 = i8::MAX as f64;
+    const INTEGER: bool = true;
+
+    fn from_f64(value: f64) -> Self {
+        value as i8
+    }
+}
+
+impl SampleFormat for u8 {
+    const FULL_SCALE: f64 = i8::MAX as f64;
+    const INTEGER: bool = true;
+
+    fn from_f64(value: f64) -> Self {
+        (value + 128.) as u8
+    }
+}
+
+impl SampleFormat for i16 {
+    const FULL_SCALE: f64 = i16::MAX as f64;
+    const INTEGER: bool = true;
+
+    fn from_f64(value: f64) -> Self {
+        value as i16
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct I24(i32);
+
+impl I24 {
+    pub const MAX: i32 = (1 << 23) - 1;
+    pub const MIN: i32 = -(1 << 23);
+
+    pub fn new(value: i32) -> Option<Self> {
+        if (Self::MIN..=Self::MAX).contains(&value) {
+            Some(Self(value))
+        } else {
+            None
+        }
+    }
+
+    pub fn value(self) -> i32 {
+        self.0
+    }
+
+    pub fn to_le_bytes(self) -> [u8; 3] {
+        let [b0, b1, b2, _] = self.0.to_le_bytes();
+        [b0, b1, b2]
+    }
+}
+
+impl From<I24> for i32 {
+    fn from(value: I24) -> Self {
+        value.0
+    }
+}
+
+impl SampleFormat for I24 {
+    const FULL_SCALE: f64 = I24::MAX as f64;
+    const INTEGER: bool = true;
+
+    fn from_f64(value: f64) -> Self {
+        Self(value as i32)
+    }
+}
+
+impl SampleFormat for i32 {
+    const FULL_SCALE: f64 = i32::MAX as f64;
+    const INTEGER: bool = true;
+
+    fn from_f64(value: f64) -> Self {
+        value as i32
+    }
+}
+
+impl SampleFormat for f32 {
+    const FULL_SCALE: f64 = 1.;
+    const INTEGER: bool = false;
+
+    fn from_f64(value: f64) -> Self {
+        value as f32
+    }
+}
+
+impl SampleFormat for f64 {
+    const FULL_SCALE: f64 = 1.;
+    const INTEGER: bool = false;
+
+    fn from_f64(value: f64) -> Self {
+        value
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Rounding {
+    #[default]
+    Nearest,
+    Floor,
+    TowardZero,
+}
+
+impl Rounding {
+    #[inline]
+    fn apply(&self, value: f64) -> f64 {
+        match self {
+            Self::Nearest => value.round(),
+            Self::Floor => value.floor(),
+            Self::TowardZero => value.trunc(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Dither {
+    #[default]
+    None,
+    /// Triangular noise of ±1 LSB.
+    Tpdf,
+    /// Triangular noise with first-order error feedback, moving quantization noise towards high frequencies.
+    TpdfShaped,
+}
+
+pub struct Converter {
+    rounding: Rounding,
+    dither: Dither,
+    rng: StdRng,
+    error: f64,
+}
+
+impl Default for Converter {
+    fn default() -> Self {
+        Self::new(Rounding::default(), Dither::default())
+    }
+}
+
+impl Converter {
+    pub fn new(rounding: Rounding, dither: Dither) -> Self {
+        Self {
+            rounding,
+            dither,
+            rng: StdRng::from_entropy(),
+            error: 0.,
+        }
+    }
+
+    pub fn convert<T>(&mut self, sample: f64) -> T
+    where
+        T: SampleFormat,
+    {
+        if !T::INTEGER {
+            return T::from_f64(sample.clamp(-1., 1.));
+        }
+        T::from_f64(self.level(sample, T::FULL_SCALE))
+    }
+
+    /// Rounds and dithers `sample` to a multiple of `1 / full_scale`, staying in floating point.
+    pub fn quantize(&mut self, sample: f64, full_scale: f64) -> f64 {
+        self.level(sample, full_scale) / full_scale
+    }
+
+    /// Integer level of `sample` on a scale of `full_scale`, clamped like a two's complement format.
+    fn level(&mut self, sample: f64, full_scale: f64) -> f64 {
+        let scaled = sample * full_scale;
+        let target = match self.dither {
+            Dither::None => scaled,
+            Dither::Tpdf => scaled + self.tpdf(),
+            Dither::TpdfShaped => scaled - self.error + self.tpdf(),
+        };
+        let quantized = self
+            .rounding
+            .apply(target)
+            .clamp(-full_scale - 1., full_scale);
+        if let Dither::TpdfShaped = self.dither {
+            self.error = (quantized - (scaled - self.error)).clamp(-2., 2.);
+        }
+        quantized
+    }
+
+    #[inline]
+    fn tpdf(&mut self) -> f64 {
+        let lsb = Uniform::new(-0.5, 0.5);
+        self.rng.sample(lsb) + self.rng.sample(lsb)
+    }
+}
@@ -0,0 +1,61 @@
+use crate::effect::{filter::Biquad, DelayLine, Effect};
+
+/// Feedback delay (echo), with an optional filter in the feedback loop.
+pub struct Delay {
+    line: DelayLine,
+    sample_rate: f64,
+    delay: f64,
+    feedback: f64,
+    mix: f64,
+    filter: Option<Biquad>,
+}
+
+impl Delay {
+    /// `time` is in seconds. `feedback` is clamped to [-0.99, 0.99] and `mix` to [0, 1].
+    pub fn new(sample_rate: u32, time: f64, feedback: f64, mix: f64) -> Self {
+        let sample_rate = sample_rate as f64;
+        let delay = (time.max(0.) * sample_rate).max(1.);
+        Self {
+            line: DelayLine::new(delay.ceil() as usize + 1),
+            sample_rate,
+            delay,
+            feedback: feedback.clamp(-0.99, 0.99),
+            mix: mix.clamp(0., 1.),
+            filter: None,
+        }
+    }
+
+    /// Filters each repeat, e.g. with a low-pass for echoes that darken as they fade.
+    pub fn with_filter(self, filter: Biquad) -> Self {
+        Self {
+            filter: Some(filter),
+            ..self
+        }
+    }
+}
+
+impl Effect for Delay {
+    fn process(&mut self, sample: f64) -> f64 {
+        let delayed = self.line.read(self.delay - 1.);
+        let repeat = match &mut self.filter {
+            Some(filter) => filter.process(delayed),
+            None => delayed,
+        };
+        self.line.push(sample + self.feedback * repeat);
+        crate::lerp(sample, delayed, self.mix)
+    }
+
+    /// Time for the repeats to fade by 60 dB, assuming each one is amplified by the peak
+    /// gain of the filter; infinite when that makes them grow instead.
+    fn tail(&self) -> f64 {
+        let gain = self.feedback.abs() * self.filter.as_ref().map_or(1., Biquad::peak_gain);
+        let repeats = if gain == 0. {
+            1.
+        } else if gain >= 1. {
+            return f64::INFINITY;
+        } else {
+            1. + (-3. / gain.log10()).ceil()
+        };
+        repeats * self.delay / self.sample_rate
+    }
+}
@@ -0,0 +1,43 @@
+use crate::{effect::Effect, envelope::Envelope, traits::Proc};
+
+pub struct Downsample {
+    target_rate: Envelope,
+    dt: f64,
+    t: f64,
+    phase: f64,
+    held: f64,
+}
+
+impl Downsample {
+    pub fn new(sample_rate: u32, target_rate: Envelope) -> Self {
+        Self {
+            target_rate,
+            dt: 1. / sample_rate as f64,
+            t: 0.,
+            phase: 1.,
+            held: 0.,
+        }
+    }
+
+    pub fn new_simple(sample_rate: u32, target_rate: f64) -> Option<Self> {
+        if !target_rate.is_normal() || target_rate <= 0. {
+            None
+        } else {
+            let target_rate =
+                Envelope::from_duration(target_rate, 0., f64::INFINITY, 0., 0., None).unwrap();
+            Some(Self::new(sample_rate, target_rate))
+        }
+    }
+}
+
+impl Effect for Downsample {
+    fn process(&mut self, sample: f64) -> f64 {
+        if self.phase >= 1. {
+            self.phase = self.phase.fract();
+            self.held = sample;
+        }
+        self.phase += self.target_rate.value(self.t) * self.dt;
+        self.t += self.dt;
+        self.held
+    }
+}
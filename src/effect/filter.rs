@@ -0,0 +1,148 @@
+use std::f64::consts::{FRAC_1_SQRT_2, TAU};
+
+use crate::{control::Param, effect::Effect};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterType {
+    LowPass,
+    HighPass,
+}
+
+/// Second-order resonant filter (RBJ cookbook).
+pub struct Biquad {
+    filter_type: FilterType,
+    sample_rate: f64,
+    cutoff_freq: f64,
+    q: f64,
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+    cutoff_control: Option<Param>,
+    t: f64,
+}
+
+impl Biquad {
+    /// Relative change of a controlled cutoff below which the coefficients are kept.
+    const CUTOFF_TOLERANCE: f64 = 1e-3;
+
+    pub fn new(filter_type: FilterType, sample_rate: u32, cutoff_freq: f64, q: f64) -> Self {
+        let mut filter = Self {
+            filter_type,
+            sample_rate: sample_rate as f64,
+            cutoff_freq,
+            q: if q.is_normal() && q > 0. {
+                q
+            } else {
+                FRAC_1_SQRT_2
+            },
+            b: [0.; 3],
+            a: [0.; 2],
+            x: [0.; 2],
+            y: [0.; 2],
+            cutoff_control: None,
+            t: 0.,
+        };
+        filter.set_cutoff(cutoff_freq);
+        filter
+    }
+
+    pub fn low_pass(sample_rate: u32, cutoff_freq: f64) -> Self {
+        Self::new(FilterType::LowPass, sample_rate, cutoff_freq, FRAC_1_SQRT_2)
+    }
+
+    pub fn high_pass(sample_rate: u32, cutoff_freq: f64) -> Self {
+        Self::new(
+            FilterType::HighPass,
+            sample_rate,
+            cutoff_freq,
+            FRAC_1_SQRT_2,
+        )
+    }
+
+    /// Follows `param` for the cutoff frequency, which can be changed while the sound plays.
+    pub fn with_cutoff_control(self, param: Param) -> Self {
+        Self {
+            cutoff_control: Some(param),
+            ..self
+        }
+    }
+
+    pub fn cutoff(&self) -> f64 {
+        self.cutoff_freq
+    }
+
+    /// Highest gain of the frequency response, above unity when `q` makes it resonate.
+    pub fn peak_gain(&self) -> f64 {
+        // the bilinear transform warps frequencies but keeps the analog prototype's peak
+        if self.q <= FRAC_1_SQRT_2 {
+            1.
+        } else {
+            self.q / (1. - 0.25 / (self.q * self.q)).sqrt()
+        }
+    }
+
+    /// Sets the cutoff frequency, clamped between 1 Hz and just below Nyquist.
+    pub fn set_cutoff(&mut self, cutoff_freq: f64) {
+        let cutoff_freq = self.clamp_cutoff(cutoff_freq);
+        self.cutoff_freq = cutoff_freq;
+
+        let w0 = TAU * cutoff_freq / self.sample_rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2. * self.q);
+        let a0 = 1. + alpha;
+        let b = match self.filter_type {
+            FilterType::LowPass => [(1. - cos) / 2., 1. - cos, (1. - cos) / 2.],
+            FilterType::HighPass => [(1. + cos) / 2., -(1. + cos), (1. + cos) / 2.],
+        };
+        self.b = [b[0] / a0, b[1] / a0, b[2] / a0];
+        self.a = [-2. * cos / a0, (1. - alpha) / a0];
+    }
+
+    fn max_cutoff(&self) -> f64 {
+        self.sample_rate / 2. * 0.99
+    }
+
+    fn clamp_cutoff(&self, cutoff_freq: f64) -> f64 {
+        if cutoff_freq.is_nan() {
+            self.sample_rate / 2.
+        } else {
+            cutoff_freq.clamp(1., self.max_cutoff())
+        }
+    }
+
+    /// Advances the cutoff control, if any; `false` while it is at the highest cutoff
+    /// or above, up to Nyquist, and the filter is bypassed.
+    fn follow_cutoff(&mut self) -> bool {
+        let Some(param) = &self.cutoff_control else {
+            return true;
+        };
+        let cutoff = param.value(self.t);
+        self.t += 1. / self.sample_rate;
+        if cutoff >= self.max_cutoff() {
+            return false;
+        }
+        let cutoff = self.clamp_cutoff(cutoff);
+        if (cutoff - self.cutoff_freq).abs() > self.cutoff_freq * Self::CUTOFF_TOLERANCE {
+            self.set_cutoff(cutoff);
+        }
+        true
+    }
+}
+
+impl Effect for Biquad {
+    fn process(&mut self, sample: f64) -> f64 {
+        if !self.follow_cutoff() {
+            // keep the history so the filter resumes without a click
+            self.x = [sample, self.x[0]];
+            self.y = [sample, self.y[0]];
+            return sample;
+        }
+        let y = self.b[0] * sample + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [sample, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
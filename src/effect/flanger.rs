@@ -0,0 +1,61 @@
+use std::f64::consts::TAU;
+
+use crate::effect::{DelayLine, Effect};
+
+pub struct Flanger {
+    line: DelayLine,
+    sample_rate: f64,
+    delay: f64,
+    depth: f64,
+    rate: f64,
+    feedback: f64,
+    mix: f64,
+    t: f64,
+}
+
+impl Flanger {
+    /// `delay` and `depth` are in seconds, `rate` is the sweep frequency in Hz.
+    /// `feedback` is clamped to (-1, 1) and `mix` to [0, 1].
+    pub fn new(
+        sample_rate: u32,
+        delay: f64,
+        depth: f64,
+        rate: f64,
+        feedback: f64,
+        mix: f64,
+    ) -> Self {
+        let sample_rate = sample_rate as f64;
+        let delay = delay.max(0.);
+        let depth = depth.clamp(0., delay);
+        Self {
+            line: DelayLine::new(((delay + depth) * sample_rate).ceil() as usize + 2),
+            sample_rate,
+            delay,
+            depth,
+            rate: rate.max(0.),
+            feedback: feedback.clamp(-0.99, 0.99),
+            mix: mix.clamp(0., 1.),
+            t: 0.,
+        }
+    }
+}
+
+impl Effect for Flanger {
+    fn process(&mut self, sample: f64) -> f64 {
+        let delay = self.delay + self.depth * (TAU * self.rate * self.t).sin();
+        let delayed = self.line.read(delay * self.sample_rate);
+        self.line.push(sample + self.feedback * delayed);
+        self.t += 1. / self.sample_rate;
+        crate::lerp(sample, delayed, self.mix)
+    }
+
+    /// Time for the recirculating signal to fade by 60 dB, at the longest delay.
+    fn tail(&self) -> f64 {
+        let repeats = if self.feedback == 0. {
+            1.
+        } else {
+            1. + (-3. / self.feedback.abs().log10()).ceil()
+        };
+        repeats * self.line.len() as f64 / self.sample_rate
+    }
+}
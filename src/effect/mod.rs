@@ -0,0 +1,223 @@
+pub mod delay;
+pub mod downsample;
+pub mod filter;
+pub mod flanger;
+pub mod phaser;
+pub mod reverb;
+pub mod waveshaper;
+
+use crate::traits::{Duration, SampleRate, Synth};
+
+use crate::envelope::Envelope;
+use delay::Delay;
+use downsample::Downsample;
+use filter::Biquad;
+use flanger::Flanger;
+use phaser::Phaser;
+use reverb::{Convolution, Freeverb};
+use waveshaper::{Shape, Waveshaper};
+
+pub trait Effect: Send {
+    fn process(&mut self, sample: f64) -> f64;
+
+    /// Time in seconds the effect keeps sounding after its input ends.
+    fn tail(&self) -> f64 {
+        0.
+    }
+}
+
+pub struct Processed<S, F>
+where
+    S: Synth,
+    F: Effect,
+{
+    sound: S,
+    effect: F,
+    tail: Option<usize>,
+}
+
+impl<S, F> Processed<S, F>
+where
+    S: Synth,
+    F: Effect,
+{
+    pub fn new(sound: S, effect: F) -> Self {
+        Self {
+            sound,
+            effect,
+            tail: None,
+        }
+    }
+
+    pub fn effect(&self) -> &F {
+        &self.effect
+    }
+
+    pub fn effect_mut(&mut self) -> &mut F {
+        &mut self.effect
+    }
+}
+
+impl<S, F> Synth for Processed<S, F>
+where
+    S: Synth,
+    F: Effect,
+{
+}
+
+impl<S, F> Duration for Processed<S, F>
+where
+    S: Synth,
+    F: Effect,
+{
+    fn duration(&self) -> f64 {
+        self.sound.duration() + self.effect.tail()
+    }
+}
+
+impl<S, F> SampleRate for Processed<S, F>
+where
+    S: Synth,
+    F: Effect,
+{
+    fn sample_rate(&self) -> u32 {
+        self.sound.sample_rate()
+    }
+}
+
+impl<S, F> Iterator for Processed<S, F>
+where
+    S: Synth,
+    F: Effect,
+{
+    type Item = f64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let input = match self.tail {
+            None => match self.sound.next() {
+                Some(sample) => sample,
+                None => {
+                    let tail = self.effect.tail() * self.sound.sample_rate() as f64;
+                    self.tail = Some(tail.ceil() as usize);
+                    return self.next();
+                }
+            },
+            Some(0) => return None,
+            Some(ref mut remaining) => {
+                *remaining -= 1;
+                0.
+            }
+        };
+        Some(self.effect.process(input))
+    }
+}
+
+pub trait EffectChain: Synth + Sized {
+    fn effect<F>(self, effect: F) -> Processed<Self, F>
+    where
+        F: Effect,
+    {
+        Processed::new(self, effect)
+    }
+
+    fn low_pass(self, cutoff_freq: f64) -> Processed<Self, Biquad> {
+        let filter = Biquad::low_pass(self.sample_rate(), cutoff_freq);
+        self.effect(filter)
+    }
+
+    fn high_pass(self, cutoff_freq: f64) -> Processed<Self, Biquad> {
+        let filter = Biquad::high_pass(self.sample_rate(), cutoff_freq);
+        self.effect(filter)
+    }
+
+    fn downsample(self, target_rate: Envelope) -> Processed<Self, Downsample> {
+        let downsample = Downsample::new(self.sample_rate(), target_rate);
+        self.effect(downsample)
+    }
+
+    fn delay(self, time: f64, feedback: f64, mix: f64) -> Processed<Self, Delay> {
+        let delay = Delay::new(self.sample_rate(), time, feedback, mix);
+        self.effect(delay)
+    }
+
+    /// Shapes with `shape` after multiplying by `drive`; returns `None` for an invalid curve.
+    fn distort(self, shape: Shape, drive: f64, mix: f64) -> Option<Processed<Self, Waveshaper>> {
+        let waveshaper = Waveshaper::new(shape, drive, mix)?;
+        Some(self.effect(waveshaper))
+    }
+
+    fn flanger(
+        self,
+        delay: f64,
+        depth: f64,
+        rate: f64,
+        feedback: f64,
+        mix: f64,
+    ) -> Processed<Self, Flanger> {
+        let flanger = Flanger::new(self.sample_rate(), delay, depth, rate, feedback, mix);
+        self.effect(flanger)
+    }
+
+    fn phaser(
+        self,
+        stages: usize,
+        center: Envelope,
+        feedback: f64,
+        mix: f64,
+    ) -> Processed<Self, Phaser> {
+        let phaser = Phaser::new(self.sample_rate(), stages, center, feedback, mix);
+        self.effect(phaser)
+    }
+
+    fn sfxr_phaser(self, offset: f64, sweep: f64) -> Processed<Self, Phaser> {
+        let phaser = Phaser::from_sfxr(self.sample_rate(), offset, sweep);
+        self.effect(phaser)
+    }
+
+    fn convolve(self, impulse: crate::Samples<f64>, mix: f64) -> Processed<Self, Convolution> {
+        let convolution = Convolution::new(self.sample_rate(), impulse, mix);
+        self.effect(convolution)
+    }
+
+    fn reverb(self, room_size: f64, damping: f64, mix: f64) -> Processed<Self, Freeverb> {
+        let reverb = Freeverb::new(self.sample_rate(), room_size, damping, mix);
+        self.effect(reverb)
+    }
+}
+
+impl<S> EffectChain for S where S: Synth {}
+
+/// Circular buffer with fractional, linearly interpolated reads.
+pub(crate) struct DelayLine {
+    buffer: Vec<f64>,
+    position: usize,
+}
+
+impl DelayLine {
+    pub(crate) fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.; len.max(1)],
+            position: 0,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub(crate) fn push(&mut self, sample: f64) {
+        self.position = (self.position + 1) % self.buffer.len();
+        self.buffer[self.position] = sample;
+    }
+
+    /// Sample pushed `delay` samples ago; `delay` is clamped to the buffer length.
+    pub(crate) fn read(&self, delay: f64) -> f64 {
+        let len = self.buffer.len();
+        let delay = delay.clamp(0., (len - 1) as f64);
+        let whole = delay.floor() as usize;
+        let frac = delay - whole as f64;
+        let a = self.buffer[(self.position + len - whole) % len];
+        let b = self.buffer[(self.position + 2 * len - whole - 1) % len];
+        crate::lerp(a, b, frac)
+    }
+}
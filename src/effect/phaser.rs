@@ -0,0 +1,114 @@
+use std::f64::consts::{PI, TAU};
+
+use crate::{effect::Effect, envelope::Envelope, traits::Proc};
+
+pub struct Phaser {
+    sample_rate: f64,
+    center: Envelope,
+    lfo_rate: f64,
+    lfo_depth: f64,
+    feedback: f64,
+    mix: f64,
+    /// Input and output of each all-pass stage at the previous sample.
+    stages: Vec<(f64, f64)>,
+    last: f64,
+    t: f64,
+}
+
+impl Phaser {
+    /// `center` is the break frequency of the all-pass stages in Hz; with `n` stages the first
+    /// notch sits at `center * tan(PI / (2 * n))`. `stages` is clamped to [1, 32],
+    /// `feedback` to (-1, 1) and `mix` to [0, 1].
+    pub fn new(sample_rate: u32, stages: usize, center: Envelope, feedback: f64, mix: f64) -> Self {
+        Self {
+            sample_rate: sample_rate as f64,
+            center,
+            lfo_rate: 0.,
+            lfo_depth: 0.,
+            feedback: feedback.clamp(-0.99, 0.99),
+            mix: mix.clamp(0., 1.),
+            stages: vec![(0., 0.); stages.clamp(1, 32)],
+            last: 0.,
+            t: 0.,
+        }
+    }
+
+    pub fn new_simple(
+        sample_rate: u32,
+        stages: usize,
+        center: f64,
+        feedback: f64,
+        mix: f64,
+    ) -> Option<Self> {
+        if !center.is_normal() || center <= 0. {
+            None
+        } else {
+            let center = Envelope::from_duration(center, 0., f64::INFINITY, 0., 0., None).unwrap();
+            Some(Self::new(sample_rate, stages, center, feedback, mix))
+        }
+    }
+
+    /// Four stages swept like the phaser of sfxr, whose `offset` and `sweep` range over [-1, 1].
+    ///
+    /// sfxr mixes its input with a copy delayed by `offset² * 1020` samples at 8 × 44100 Hz,
+    /// the delay changing by `sweep²` such samples per 44100 Hz output sample;
+    /// the first notch of that comb is followed.
+    pub fn from_sfxr(sample_rate: u32, offset: f64, sweep: f64) -> Self {
+        const STAGES: usize = 4;
+        const RATE: f64 = 8. * 44100.;
+        const MAX_DELAY: f64 = 1023.;
+
+        let start = offset.clamp(-1., 1.).powi(2).copysign(offset) * 1020.;
+        let speed = sweep.clamp(-1., 1.).powi(2).copysign(sweep) * 44100.;
+        let end = if speed == 0. {
+            0.
+        } else {
+            (MAX_DELAY.copysign(speed) - start) / speed
+        };
+        let center = |t: f64| {
+            let delay = (start + speed * t).abs().clamp(1., MAX_DELAY);
+            RATE / (2. * delay) / (PI / (2 * STAGES) as f64).tan()
+        };
+        let points = if end > 0. {
+            const POINTS: usize = 64;
+            (0..=POINTS)
+                .map(|i| end * i as f64 / POINTS as f64)
+                .map(|t| (t, center(t)))
+                .chain([(f64::INFINITY, center(end))])
+                .collect()
+        } else {
+            vec![(0., center(0.)), (f64::INFINITY, center(0.))]
+        };
+        let center = Envelope::from_points(points, None).unwrap();
+        Self::new(sample_rate, STAGES, center, 0., 0.5)
+    }
+
+    /// Modulates the center frequency by `depth` octaves at `rate` Hz.
+    pub fn with_lfo(self, rate: f64, depth: f64) -> Self {
+        Self {
+            lfo_rate: rate.max(0.),
+            lfo_depth: depth.max(0.),
+            ..self
+        }
+    }
+}
+
+impl Effect for Phaser {
+    fn process(&mut self, sample: f64) -> f64 {
+        let lfo = self.lfo_depth * (TAU * self.lfo_rate * self.t).sin();
+        let center = (self.center.value(self.t) * lfo.exp2()).clamp(1., 0.49 * self.sample_rate);
+        let k = (PI * center / self.sample_rate).tan();
+        let a = (k - 1.) / (k + 1.);
+
+        let mut x = sample + self.feedback * self.last;
+        for (x1, y1) in self.stages.iter_mut() {
+            let y = a * x + *x1 - a * *y1;
+            *x1 = x;
+            *y1 = y;
+            x = y;
+        }
+        self.last = x;
+        self.t += 1. / self.sample_rate;
+        crate::lerp(sample, x, self.mix)
+    }
+}
@@ -0,0 +1,240 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use realfft::{
+    num_complex::Complex, num_traits::Zero, ComplexToReal, RealFftPlanner, RealToComplex,
+};
+
+use crate::{effect::Effect, resample::Resamplable, Samples};
+
+/// Uniformly partitioned convolution with an impulse response. The first partition is
+/// convolved directly and the rest in the frequency domain, so there is no added latency.
+pub struct Convolution {
+    sample_rate: f64,
+    /// Length of the impulse response, in samples.
+    len: usize,
+    head: Vec<f64>,
+    /// Spectra of the partitions after the first.
+    partitions: Vec<Vec<Complex<f64>>>,
+    /// Spectra of the most recent input blocks, latest first.
+    inputs: VecDeque<Vec<Complex<f64>>>,
+    forward: Arc<dyn RealToComplex<f64>>,
+    inverse: Arc<dyn ComplexToReal<f64>>,
+    previous: Vec<f64>,
+    current: Vec<f64>,
+    tail: Vec<f64>,
+    position: usize,
+    mix: f64,
+}
+
+impl Convolution {
+    const BLOCK: usize = 128;
+
+    /// `impulse` is resampled to `sample_rate` if needed; `mix` is clamped to [0, 1].
+    pub fn new(sample_rate: u32, impulse: Samples<f64>, mix: f64) -> Self {
+        let impulse = impulse.resample(sample_rate).samples;
+        let block = Self::BLOCK;
+        let mut planner = RealFftPlanner::<f64>::new();
+        let forward = planner.plan_fft_forward(2 * block);
+        let inverse = planner.plan_fft_inverse(2 * block);
+
+        let mut head = impulse.iter().take(block).copied().collect::<Vec<_>>();
+        head.resize(block, 0.);
+        let partitions: Vec<_> = impulse
+            .chunks(block)
+            .skip(1)
+            .map(|chunk| {
+                let mut padded = chunk.to_vec();
+                padded.resize(2 * block, 0.);
+                let mut spectrum = forward.make_output_vec();
+                forward.process(&mut padded, &mut spectrum).unwrap();
+                spectrum
+            })
+            .collect();
+        let inputs = (0..partitions.len())
+            .map(|_| forward.make_output_vec())
+            .collect();
+
+        Self {
+            sample_rate: sample_rate as f64,
+            len: impulse.len(),
+            head,
+            partitions,
+            inputs,
+            forward,
+            inverse,
+            previous: vec![0.; block],
+            current: vec![0.; block],
+            tail: vec![0.; block],
+            position: 0,
+            mix: mix.clamp(0., 1.),
+        }
+    }
+
+    /// Output of the partitions after the first over the next block, from the inputs so far.
+    fn convolve_tail(&mut self) {
+        if self.partitions.is_empty() {
+            return;
+        }
+        let mut sum = self.forward.make_output_vec();
+        for (input, partition) in self.inputs.iter().zip(&self.partitions) {
+            sum.iter_mut()
+                .zip(input.iter().zip(partition))
+                .for_each(|(s, (x, h))| *s += x * h);
+        }
+        let mut output = self.inverse.make_output_vec();
+        self.inverse.process(&mut sum, &mut output).unwrap();
+        let scale = 1. / output.len() as f64;
+        self.tail
+            .iter_mut()
+            .zip(&output[Self::BLOCK..])
+            .for_each(|(t, y)| *t = y * scale);
+    }
+
+    fn push_block(&mut self) {
+        if !self.partitions.is_empty() {
+            let mut input = [self.previous.as_slice(), self.current.as_slice()].concat();
+            let mut spectrum = self.inputs.pop_back().unwrap_or_default();
+            spectrum.resize(Self::BLOCK + 1, Complex::zero());
+            self.forward.process(&mut input, &mut spectrum).unwrap();
+            self.inputs.push_front(spectrum);
+        }
+        std::mem::swap(&mut self.previous, &mut self.current);
+    }
+}
+
+impl Effect for Convolution {
+    fn process(&mut self, sample: f64) -> f64 {
+        let r = self.position;
+        if r == 0 {
+            self.convolve_tail();
+        }
+        self.current[r] = sample;
+        let direct: f64 = self
+            .head
+            .iter()
+            .enumerate()
+            .map(|(j, h)| {
+                let x = if j <= r {
+                    self.current[r - j]
+                } else {
+                    self.previous[Self::BLOCK + r - j]
+                };
+                h * x
+            })
+            .sum();
+        let wet = direct + self.tail[r];
+
+        self.position += 1;
+        if self.position == Self::BLOCK {
+            self.push_block();
+            self.position = 0;
+        }
+        crate::lerp(sample, wet, self.mix)
+    }
+
+    fn tail(&self) -> f64 {
+        self.len as f64 / self.sample_rate
+    }
+}
+
+/// Lowpass-feedback comb filter of Freeverb.
+struct Comb {
+    buffer: Vec<f64>,
+    index: usize,
+    store: f64,
+}
+
+impl Comb {
+    fn process(&mut self, input: f64, feedback: f64, damp: f64) -> f64 {
+        let output = self.buffer[self.index];
+        self.store = output * (1. - damp) + self.store * damp;
+        self.buffer[self.index] = input + self.store * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+/// Schroeder all-pass filter of Freeverb.
+struct AllPass {
+    buffer: Vec<f64>,
+    index: usize,
+}
+
+impl AllPass {
+    const FEEDBACK: f64 = 0.5;
+
+    fn process(&mut self, input: f64) -> f64 {
+        let delayed = self.buffer[self.index];
+        self.buffer[self.index] = input + delayed * Self::FEEDBACK;
+        self.index = (self.index + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+/// Mono Freeverb: eight parallel damped combs followed by four all-passes.
+pub struct Freeverb {
+    combs: Vec<Comb>,
+    allpasses: Vec<AllPass>,
+    feedback: f64,
+    damp: f64,
+    mix: f64,
+    tail: f64,
+}
+
+impl Freeverb {
+    /// Delays in samples at 44100 Hz.
+    const COMBS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+    const ALLPASSES: [usize; 4] = [556, 441, 341, 225];
+    const INPUT_GAIN: f64 = 0.015;
+    const WET_GAIN: f64 = 3.;
+
+    /// `room_size`, `damping` and `mix` are clamped to [0, 1].
+    pub fn new(sample_rate: u32, room_size: f64, damping: f64, mix: f64) -> Self {
+        let scale = sample_rate as f64 / 44100.;
+        let delay = |d: usize| ((d as f64 * scale).round() as usize).max(1);
+        let feedback = 0.7 + 0.28 * room_size.clamp(0., 1.);
+        // Time for the longest comb to decay by 60 dB.
+        let longest = delay(Self::COMBS[7]) as f64 / sample_rate as f64;
+        Self {
+            combs: Self::COMBS
+                .iter()
+                .map(|&d| Comb {
+                    buffer: vec![0.; delay(d)],
+                    index: 0,
+                    store: 0.,
+                })
+                .collect(),
+            allpasses: Self::ALLPASSES
+                .iter()
+                .map(|&d| AllPass {
+                    buffer: vec![0.; delay(d)],
+                    index: 0,
+                })
+                .collect(),
+            feedback,
+            damp: 0.4 * damping.clamp(0., 1.),
+            mix: mix.clamp(0., 1.),
+            tail: longest * -3. / feedback.log10(),
+        }
+    }
+}
+
+impl Effect for Freeverb {
+    fn process(&mut self, sample: f64) -> f64 {
+        let input = sample * Self::INPUT_GAIN;
+        let (feedback, damp) = (self.feedback, self.damp);
+        let mut wet: f64 = self
+            .combs
+            .iter_mut()
+            .map(|comb| comb.process(input, feedback, damp))
+            .sum();
+        for allpass in self.allpasses.iter_mut() {
+            wet = allpass.process(wet);
+        }
+        crate::lerp(sample, wet * Self::WET_GAIN, self.mix)
+    }
+
+    fn tail(&self) -> f64 {
+        self.tail
+    }
+}
@@ -0,0 +1,89 @@
+use crate::effect::Effect;
+
+/// Transfer curve of a [`Waveshaper`].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(tag = "type", rename_all = "lowercase"))]
+pub enum Shape {
+    /// `tanh`, saturating smoothly.
+    #[default]
+    Tanh,
+    /// Clamps to [-1, 1], like [`crate::waveform::Tangent`].
+    HardClip,
+    /// Reflects back from ±1 instead of clipping.
+    Foldback,
+    /// Output values evenly spaced over inputs from -1 to 1, linearly interpolated;
+    /// inputs beyond are clamped.
+    Curve { points: Vec<f64> },
+}
+
+impl Shape {
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Self::Curve { points } => points.len() >= 2 && points.iter().all(|p| p.is_finite()),
+            _ => true,
+        }
+    }
+
+    pub fn apply(&self, x: f64) -> f64 {
+        match self {
+            Self::Tanh => x.tanh(),
+            Self::HardClip => x.clamp(-1., 1.),
+            Self::Foldback => {
+                let t = (x + 1.).rem_euclid(4.);
+                if t < 2. {
+                    t - 1.
+                } else {
+                    3. - t
+                }
+            }
+            Self::Curve { points } => {
+                let position = (x.clamp(-1., 1.) + 1.) / 2. * (points.len() - 1) as f64;
+                let i = (position as usize).min(points.len() - 2);
+                crate::lerp(points[i], points[i + 1], position - i as f64)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Waveshaper {
+    #[cfg_attr(feature = "serde", serde(default))]
+    shape: Shape,
+    #[cfg_attr(feature = "serde", serde(default = "Waveshaper::unity"))]
+    drive: f64,
+    #[cfg_attr(feature = "serde", serde(default = "Waveshaper::unity"))]
+    mix: f64,
+}
+
+impl Waveshaper {
+    /// `drive` is the gain applied before shaping; `mix` is clamped to [0, 1].
+    pub fn new(shape: Shape, drive: f64, mix: f64) -> Option<Self> {
+        let waveshaper = Self {
+            shape,
+            drive,
+            mix: mix.clamp(0., 1.),
+        };
+        waveshaper.is_valid().then_some(waveshaper)
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.shape.is_valid()
+            && self.drive.is_finite()
+            && self.drive >= 0.
+            && (0. ..=1.).contains(&self.mix)
+    }
+
+    #[cfg(feature = "serde")]
+    fn unity() -> f64 {
+        1.
+    }
+}
+
+impl Effect for Waveshaper {
+    fn process(&mut self, sample: f64) -> f64 {
+        let shaped = self.shape.apply(sample * self.drive);
+        crate::lerp(sample, shaped, self.mix)
+    }
+}
@@ -1,18 +1,97 @@
+use crate::control::Param;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Curve {
+    #[default]
+    Linear,
+    /// Constant ratio between successive values; an endpoint at zero is approached as if it were 60 dB below the other.
+    Exponential,
+    /// Mirror image of [`Curve::Exponential`].
+    Logarithmic,
+    /// Progress raised to the given power.
+    Power(f64),
+    /// Cubic Bézier easing through the given two control values.
+    Bezier(f64, f64),
+}
+
+impl Curve {
+    const ZERO_RATIO: f64 = 1e-3;
+
+    pub fn is_valid(&self) -> bool {
+        match *self {
+            Self::Power(k) => k.is_normal() && k > 0.,
+            Self::Bezier(c1, c2) => c1.is_finite() && c2.is_finite(),
+            _ => true,
+        }
+    }
+
+    /// Fraction of the way from `start` to `end` after progress `p` in `[0, 1]`.
+    fn shape(&self, start: f64, end: f64, p: f64) -> f64 {
+        match *self {
+            Self::Linear => p,
+            Self::Exponential => Self::exponential(Self::ratio(start, end), p),
+            Self::Logarithmic => 1. - Self::exponential(Self::ratio(start, end), 1. - p),
+            Self::Power(k) => p.powf(k),
+            Self::Bezier(c1, c2) => {
+                let q = 1. - p;
+                3. * q * q * p * c1 + 3. * q * p * p * c2 + p * p * p
+            }
+        }
+    }
+
+    fn ratio(start: f64, end: f64) -> f64 {
+        if start * end > 0. {
+            end / start
+        } else if start == end {
+            1.
+        } else if end.abs() < start.abs() {
+            Self::ZERO_RATIO
+        } else {
+            1. / Self::ZERO_RATIO
+        }
+    }
+
+    #[inline]
+    fn exponential(ratio: f64, p: f64) -> f64 {
+        if (ratio - 1.).abs() < 1e-9 {
+            p
+        } else {
+            (ratio.powf(p) - 1.) / (ratio - 1.)
+        }
+    }
+}
+
 pub struct Interval {
     duration: f64,
     start: f64,
     end: f64,
+    curve: Curve,
 }
 
 impl Interval {
     pub fn value(&self, t: f64) -> f64 {
         if t >= 0. && t <= self.duration {
-            crate::lerp(self.start, self.end, t / self.duration)
+            let p = self.curve.shape(self.start, self.end, t / self.duration);
+            crate::lerp(self.start, self.end, p)
         } else {
             0.
         }
     }
 
+    pub fn with_curve(self, curve: Curve) -> Option<Self> {
+        if curve.is_valid() {
+            Some(Self { curve, ..self })
+        } else {
+            None
+        }
+    }
+
     pub fn new(duration: f64, start: f64, end: f64) -> Option<Self> {
         if duration.is_nan()
             || duration <= 0.
@@ -25,43 +104,280 @@ impl Interval {
                 duration,
                 start,
                 end,
+                curve: Curve::Linear,
             })
         }
     }
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct Release {
+    requested: Arc<AtomicBool>,
+}
+
+impl Release {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn release(&self) {
+        self.requested.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_released(&self) -> bool {
+        self.requested.load(Ordering::Relaxed)
+    }
+}
+
+struct Sustain {
+    start: f64,
+    end: f64,
+    release: Release,
+    released_at: AtomicU64,
+}
+
+impl Sustain {
+    fn recorded(&self) -> Option<f64> {
+        let recorded = f64::from_bits(self.released_at.load(Ordering::Relaxed));
+        if recorded.is_nan() {
+            None
+        } else {
+            Some(recorded)
+        }
+    }
+
+    /// Records `t` as the time of release if a release was requested and none is recorded yet.
+    fn latch(&self, t: f64) {
+        if self.release.is_released() {
+            let _ = self.released_at.compare_exchange(
+                f64::NAN.to_bits(),
+                t.to_bits(),
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    fn looped(&self, t: f64) -> f64 {
+        if t < self.end {
+            t
+        } else if self.end > self.start {
+            self.start + (t - self.start) % (self.end - self.start)
+        } else {
+            self.start
+        }
+    }
+
+    /// Position within the envelope's segments at time `t`; after release, playback
+    /// continues from wherever the loop or hold was.
+    fn local_time(&self, t: f64) -> f64 {
+        match self.recorded() {
+            Some(released) if t >= released => self.looped(released) + t - released,
+            _ => self.looped(t),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnvelopeError {
+    /// The time or value of the point at `index` is NaN.
+    NaN { index: usize },
+    /// The value of the point at `index` is infinite.
+    InfiniteValue { index: usize },
+    /// The point at `index` lies before the start of the envelope.
+    NegativeTime { index: usize, time: f64 },
+    /// The point at `index` lies before the point preceding it.
+    NonMonotonicTime {
+        index: usize,
+        time: f64,
+        previous: f64,
+    },
+    /// The envelope never ends, so a one-shot sound would play forever.
+    InfiniteDuration,
+    /// The curve of the segment ending at point `index`, or of the `index`th segment of a
+    /// duration or ADSR envelope, is invalid, see [`Curve::is_valid`].
+    InvalidCurve { index: usize },
+    /// Vibrato depth must be within `[0, 1]` and its frequency non-negative.
+    InvalidVibrato { depth: f64, freq: f64 },
+    /// The named parameter of a duration or ADSR envelope is NaN or infinite.
+    NonFinite { parameter: &'static str, value: f64 },
+    /// The named segment of a duration or ADSR envelope has a negative duration.
+    NegativeDuration {
+        segment: &'static str,
+        duration: f64,
+    },
+    /// The peak amplitude of a duration or ADSR envelope must be positive.
+    NonPositiveAmplitude { amp: f64 },
+    /// The sustain level of an ADSR envelope must be non-negative.
+    NegativeSustainLevel { level: f64 },
+    /// The envelope has no points apart from time zero, or every segment of a duration
+    /// or ADSR envelope is empty.
+    ZeroDuration,
+}
+
+impl std::fmt::Display for EnvelopeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NaN { index } => write!(f, "point {} has a NaN time or value", index),
+            Self::InfiniteValue { index } => write!(f, "point {} has an infinite value", index),
+            Self::NegativeTime { index, time } => {
+                write!(f, "point {} has negative time {}", index, time)
+            }
+            Self::NonMonotonicTime {
+                index,
+                time,
+                previous,
+            } => write!(
+                f,
+                "point {} at time {} precedes the previous point at {}",
+                index, time, previous
+            ),
+            Self::InfiniteDuration => write!(f, "envelope duration is infinite"),
+            Self::InvalidCurve { index } => write!(f, "point {} has an invalid curve", index),
+            Self::InvalidVibrato { depth, freq } => write!(
+                f,
+                "vibrato depth {} must be within [0, 1] and frequency {} non-negative",
+                depth, freq
+            ),
+            Self::NonFinite { parameter, value } => {
+                write!(f, "{} must be finite, got {}", parameter, value)
+            }
+            Self::NegativeDuration { segment, duration } => {
+                write!(f, "{} has negative duration {}", segment, duration)
+            }
+            Self::NonPositiveAmplitude { amp } => {
+                write!(f, "amplitude {} must be positive", amp)
+            }
+            Self::NegativeSustainLevel { level } => {
+                write!(f, "sustain level {} must be non-negative", level)
+            }
+            Self::ZeroDuration => write!(f, "envelope has no duration"),
+        }
+    }
+}
+
+impl std::error::Error for EnvelopeError {}
+
 pub struct Envelope {
     segments: Vec<Interval>,
+    /// Start time of each segment, for binary search.
+    starts: Vec<f64>,
+    /// Segment of the last lookup; consecutive samples usually hit it or the next one.
+    cursor: AtomicUsize,
     vibrato: Option<(f64, f64)>,
+    sustain: Option<Sustain>,
+    modulation: Option<Param>,
 }
 
 impl Envelope {
+    fn from_segments(segments: Vec<Interval>, vibrato: Option<(f64, f64)>) -> Self {
+        let starts = segments
+            .iter()
+            .scan(0., |start, s| {
+                let t = *start;
+                *start += s.duration;
+                Some(t)
+            })
+            .collect();
+        Self {
+            segments,
+            starts,
+            cursor: AtomicUsize::new(0),
+            vibrato,
+            sustain: None,
+            modulation: None,
+        }
+    }
+
     pub fn from_points(points: Vec<(f64, f64)>, vibrato: Option<(f64, f64)>) -> Option<Self> {
-        if let Some((depth, freq)) = vibrato {
-            if depth < 0. || depth > 1. || freq < 0. {
-                return None;
+        let points = points
+            .into_iter()
+            .map(|(t, v)| (t, v, Curve::Linear))
+            .collect();
+        Self::from_curves(points, vibrato)
+    }
+
+    /// Like [`Envelope::from_points`], but reports why `points` are rejected instead of
+    /// skipping them, and requires a finite duration.
+    pub fn try_from_points(
+        points: Vec<(f64, f64)>,
+        vibrato: Option<(f64, f64)>,
+    ) -> Result<Self, EnvelopeError> {
+        let points = points
+            .into_iter()
+            .map(|(t, v)| (t, v, Curve::Linear))
+            .collect();
+        Self::try_from_curves(points, vibrato)
+    }
+
+    /// Like [`Envelope::from_curves`], but reports why `points` are rejected instead of
+    /// skipping them, and requires a finite duration. Points sharing a time make a step.
+    pub fn try_from_curves(
+        points: Vec<(f64, f64, Curve)>,
+        vibrato: Option<(f64, f64)>,
+    ) -> Result<Self, EnvelopeError> {
+        Self::check_vibrato(vibrato)?;
+        let mut segments = Vec::with_capacity(points.len());
+        let (mut t_prev, mut v_prev) = (0., 0.);
+        for (index, (t, v, curve)) in points.into_iter().enumerate() {
+            if t.is_nan() || v.is_nan() {
+                return Err(EnvelopeError::NaN { index });
+            }
+            if v.is_infinite() {
+                return Err(EnvelopeError::InfiniteValue { index });
+            }
+            if t.is_infinite() {
+                return Err(EnvelopeError::InfiniteDuration);
             }
+            if t < 0. {
+                return Err(EnvelopeError::NegativeTime { index, time: t });
+            }
+            if t < t_prev {
+                return Err(EnvelopeError::NonMonotonicTime {
+                    index,
+                    time: t,
+                    previous: t_prev,
+                });
+            }
+            if !curve.is_valid() {
+                return Err(EnvelopeError::InvalidCurve { index });
+            }
+            let v = if v.is_subnormal() { 0. } else { v };
+            if let Some(s) = Interval::new(t - t_prev, v_prev, v) {
+                segments.push(s.with_curve(curve).unwrap());
+            }
+            t_prev = t;
+            v_prev = v;
+        }
+        if segments.is_empty() {
+            return Err(EnvelopeError::ZeroDuration);
         }
+        Ok(Self::from_segments(segments, vibrato))
+    }
+
+    /// Like [`Envelope::from_points`], with the curve of the segment ending at each point.
+    pub fn from_curves(
+        points: Vec<(f64, f64, Curve)>,
+        vibrato: Option<(f64, f64)>,
+    ) -> Option<Self> {
+        Self::check_vibrato(vibrato).ok()?;
         if points.is_empty() {
-            return Some(Self {
-                segments: vec![],
-                vibrato,
-            });
+            return Some(Self::from_segments(vec![], vibrato));
         }
         let mut segments = Vec::with_capacity(points.len() - 1);
         let mut points = points.into_iter();
-        let (mut t_prev, mut v_prev) = points.next()?;
+        let (mut t_prev, mut v_prev, curve) = points.next()?;
         if t_prev > 0. {
-            segments.push(Interval::new(t_prev, 0., v_prev)?);
+            segments.push(Interval::new(t_prev, 0., v_prev)?.with_curve(curve)?);
         }
-        while let Some((t, v)) = points.next() {
+        for (t, v, curve) in points {
             if let Some(s) = Interval::new(t - t_prev, v_prev, v) {
-                segments.push(s);
+                segments.push(s.with_curve(curve)?);
                 t_prev = t;
                 v_prev = v;
             }
         }
-        Some(Self { segments, vibrato })
+        Some(Self::from_segments(segments, vibrato))
     }
 
     pub fn from_duration(
@@ -72,51 +388,389 @@ impl Envelope {
         sustain_punch: f64,
         vibrato: Option<(f64, f64)>,
     ) -> Option<Self> {
-        if let Some((depth, freq)) = vibrato {
-            if depth < 0. || depth > 1. || freq < 0. {
-                return None;
+        let curves = (Curve::Linear, Curve::Linear, Curve::Linear);
+        Self::from_duration_with_curves(amp, attack, sustain, decay, sustain_punch, curves, vibrato)
+    }
+
+    /// Like [`Envelope::from_duration`], with the curves of the attack, sustain and decay segments.
+    pub fn from_duration_with_curves(
+        amp: f64,
+        attack: f64,
+        sustain: f64,
+        decay: f64,
+        sustain_punch: f64,
+        curves: (Curve, Curve, Curve),
+        vibrato: Option<(f64, f64)>,
+    ) -> Option<Self> {
+        Self::duration_with_curves(amp, attack, sustain, decay, sustain_punch, curves, vibrato).ok()
+    }
+
+    /// Like [`Envelope::from_duration`], but reports why the parameters are rejected,
+    /// and requires a finite duration.
+    pub fn try_from_duration(
+        amp: f64,
+        attack: f64,
+        sustain: f64,
+        decay: f64,
+        sustain_punch: f64,
+        vibrato: Option<(f64, f64)>,
+    ) -> Result<Self, EnvelopeError> {
+        let curves = (Curve::Linear, Curve::Linear, Curve::Linear);
+        Self::try_from_duration_with_curves(
+            amp,
+            attack,
+            sustain,
+            decay,
+            sustain_punch,
+            curves,
+            vibrato,
+        )
+    }
+
+    /// Like [`Envelope::from_duration_with_curves`], but reports why the parameters are
+    /// rejected, and requires a finite duration.
+    pub fn try_from_duration_with_curves(
+        amp: f64,
+        attack: f64,
+        sustain: f64,
+        decay: f64,
+        sustain_punch: f64,
+        curves: (Curve, Curve, Curve),
+        vibrato: Option<(f64, f64)>,
+    ) -> Result<Self, EnvelopeError> {
+        Self::duration_with_curves(amp, attack, sustain, decay, sustain_punch, curves, vibrato)?
+            .finite()
+    }
+
+    fn duration_with_curves(
+        amp: f64,
+        attack: f64,
+        sustain: f64,
+        decay: f64,
+        sustain_punch: f64,
+        curves: (Curve, Curve, Curve),
+        vibrato: Option<(f64, f64)>,
+    ) -> Result<Self, EnvelopeError> {
+        Self::check_vibrato(vibrato)?;
+        Self::check_amplitude(amp)?;
+        Self::check_finite("sustain_punch", sustain_punch)?;
+        Self::check_durations(&[("attack", attack), ("sustain", sustain), ("decay", decay)])?;
+        Self::segments_with_curves(
+            &[
+                (attack, 0., amp, curves.0),
+                (sustain, amp * (1. + sustain_punch), amp, curves.1),
+                (decay, amp, 0., curves.2),
+            ],
+            vibrato,
+        )
+    }
+
+    /// Attack to `amp`, decay to `amp * sustain_level`, hold for `sustain`, then release to zero.
+    pub fn from_adsr(
+        amp: f64,
+        attack: f64,
+        decay: f64,
+        sustain_level: f64,
+        sustain: f64,
+        release: f64,
+        vibrato: Option<(f64, f64)>,
+    ) -> Option<Self> {
+        let curves = (Curve::Linear, Curve::Linear, Curve::Linear, Curve::Linear);
+        Self::from_adsr_with_curves(
+            amp,
+            attack,
+            decay,
+            sustain_level,
+            sustain,
+            release,
+            curves,
+            vibrato,
+        )
+    }
+
+    /// Like [`Envelope::from_adsr`], with the curves of the attack, decay, sustain and release segments.
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_adsr_with_curves(
+        amp: f64,
+        attack: f64,
+        decay: f64,
+        sustain_level: f64,
+        sustain: f64,
+        release: f64,
+        curves: (Curve, Curve, Curve, Curve),
+        vibrato: Option<(f64, f64)>,
+    ) -> Option<Self> {
+        Self::adsr_with_curves(
+            amp,
+            attack,
+            decay,
+            sustain_level,
+            sustain,
+            release,
+            curves,
+            vibrato,
+        )
+        .ok()
+    }
+
+    /// Like [`Envelope::from_adsr`], but reports why the parameters are rejected,
+    /// and requires a finite duration.
+    pub fn try_from_adsr(
+        amp: f64,
+        attack: f64,
+        decay: f64,
+        sustain_level: f64,
+        sustain: f64,
+        release: f64,
+        vibrato: Option<(f64, f64)>,
+    ) -> Result<Self, EnvelopeError> {
+        let curves = (Curve::Linear, Curve::Linear, Curve::Linear, Curve::Linear);
+        Self::try_from_adsr_with_curves(
+            amp,
+            attack,
+            decay,
+            sustain_level,
+            sustain,
+            release,
+            curves,
+            vibrato,
+        )
+    }
+
+    /// Like [`Envelope::from_adsr_with_curves`], but reports why the parameters are
+    /// rejected, and requires a finite duration.
+    #[allow(clippy::too_many_arguments)]
+    pub fn try_from_adsr_with_curves(
+        amp: f64,
+        attack: f64,
+        decay: f64,
+        sustain_level: f64,
+        sustain: f64,
+        release: f64,
+        curves: (Curve, Curve, Curve, Curve),
+        vibrato: Option<(f64, f64)>,
+    ) -> Result<Self, EnvelopeError> {
+        Self::adsr_with_curves(
+            amp,
+            attack,
+            decay,
+            sustain_level,
+            sustain,
+            release,
+            curves,
+            vibrato,
+        )?
+        .finite()
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn adsr_with_curves(
+        amp: f64,
+        attack: f64,
+        decay: f64,
+        sustain_level: f64,
+        sustain: f64,
+        release: f64,
+        curves: (Curve, Curve, Curve, Curve),
+        vibrato: Option<(f64, f64)>,
+    ) -> Result<Self, EnvelopeError> {
+        Self::check_vibrato(vibrato)?;
+        Self::check_amplitude(amp)?;
+        Self::check_finite("sustain_level", sustain_level)?;
+        if sustain_level < 0. {
+            return Err(EnvelopeError::NegativeSustainLevel {
+                level: sustain_level,
+            });
+        }
+        Self::check_durations(&[
+            ("attack", attack),
+            ("decay", decay),
+            ("sustain", sustain),
+            ("release", release),
+        ])?;
+        let level = amp * sustain_level;
+        Self::segments_with_curves(
+            &[
+                (attack, 0., amp, curves.0),
+                (decay, amp, level, curves.1),
+                (sustain, level, level, curves.2),
+                (release, level, 0., curves.3),
+            ],
+            vibrato,
+        )
+    }
+
+    /// Envelope of the given `(duration, start, end, curve)` segments, skipping empty ones.
+    fn segments_with_curves(
+        segments: &[(f64, f64, f64, Curve)],
+        vibrato: Option<(f64, f64)>,
+    ) -> Result<Self, EnvelopeError> {
+        let mut intervals = vec![];
+        for (index, &(duration, start, end, curve)) in segments.iter().enumerate() {
+            if let Some(seg) = Interval::new(duration, start, end) {
+                let seg = seg
+                    .with_curve(curve)
+                    .ok_or(EnvelopeError::InvalidCurve { index })?;
+                intervals.push(seg);
+            }
+        }
+        Ok(Self::from_segments(intervals, vibrato))
+    }
+
+    fn check_vibrato(vibrato: Option<(f64, f64)>) -> Result<(), EnvelopeError> {
+        match vibrato {
+            Some((depth, freq)) if !(0. ..=1.).contains(&depth) || freq.is_nan() || freq < 0. => {
+                Err(EnvelopeError::InvalidVibrato { depth, freq })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn check_finite(parameter: &'static str, value: f64) -> Result<(), EnvelopeError> {
+        if value.is_finite() {
+            Ok(())
+        } else {
+            Err(EnvelopeError::NonFinite { parameter, value })
+        }
+    }
+
+    fn check_amplitude(amp: f64) -> Result<(), EnvelopeError> {
+        Self::check_finite("amp", amp)?;
+        if amp <= 0. {
+            Err(EnvelopeError::NonPositiveAmplitude { amp })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Segment durations may be infinite, for envelopes that hold their value forever.
+    fn check_durations(durations: &[(&'static str, f64)]) -> Result<(), EnvelopeError> {
+        for &(segment, duration) in durations {
+            if duration.is_nan() {
+                return Err(EnvelopeError::NonFinite {
+                    parameter: segment,
+                    value: duration,
+                });
+            }
+            if duration < 0. {
+                return Err(EnvelopeError::NegativeDuration { segment, duration });
             }
         }
-        if amp <= 0. || attack < 0. || sustain < 0. || decay < 0. || attack + sustain + decay <= 0.
+        if durations.iter().all(|&(_, duration)| duration == 0.) {
+            return Err(EnvelopeError::ZeroDuration);
+        }
+        Ok(())
+    }
+
+    fn finite(self) -> Result<Self, EnvelopeError> {
+        if self.total_duration().is_finite() {
+            Ok(self)
+        } else {
+            Err(EnvelopeError::InfiniteDuration)
+        }
+    }
+}
+
+impl Envelope {
+    /// Holds (`start == end`) or loops between `start` and `end` until `release` is triggered,
+    /// after which the rest of the envelope plays out. Until then the duration is infinite.
+    pub fn with_sustain(self, start: f64, end: f64, release: &Release) -> Option<Self> {
+        if start.is_nan()
+            || end.is_nan()
+            || start < 0.
+            || end < start
+            || end > self.total_duration()
         {
             return None;
         }
-        let mut segments = vec![];
-        if let Some(seg) = Interval::new(attack, 0., amp) {
-            segments.push(seg);
-        }
-        if let Some(seg) = Interval::new(sustain, amp * (1. + sustain_punch), amp) {
-            segments.push(seg);
+        Some(Self {
+            sustain: Some(Sustain {
+                start,
+                end,
+                release: release.clone(),
+                released_at: AtomicU64::new(f64::NAN.to_bits()),
+            }),
+            ..self
+        })
+    }
+
+    /// Index of the segment containing `t`, or of the first one if `t` precedes it.
+    fn segment_at(&self, t: f64) -> usize {
+        let contains = |i: usize| match (self.starts.get(i), self.starts.get(i + 1)) {
+            (Some(&start), Some(&next)) => start <= t && t < next,
+            (Some(&start), None) => start <= t,
+            (None, _) => false,
+        };
+        let cursor = self.cursor.load(Ordering::Relaxed);
+        let i = if contains(cursor) {
+            cursor
+        } else if contains(cursor + 1) {
+            cursor + 1
+        } else {
+            self.starts
+                .partition_point(|&start| start <= t)
+                .saturating_sub(1)
+        };
+        self.cursor.store(i, Ordering::Relaxed);
+        i
+    }
+
+    /// Scales the envelope by `param`, which can be changed while the sound plays.
+    pub fn with_modulation(self, param: Param) -> Self {
+        Self {
+            modulation: Some(param),
+            ..self
         }
-        if let Some(seg) = Interval::new(decay, amp, 0.) {
-            segments.push(seg);
+    }
+
+    fn total_duration(&self) -> f64 {
+        match (self.starts.last(), self.segments.last()) {
+            (Some(start), Some(s)) => start + s.duration,
+            _ => 0.,
         }
-        Some(Self { segments, vibrato })
     }
 }
 
 impl crate::traits::Proc for Envelope {
     fn value(&self, t: f64) -> f64 {
-        let mut _t = t;
-        for s in self.segments.iter() {
-            if _t < s.duration {
-                if let Some((depth, freq)) = self.vibrato {
-                    return s.value(_t) * (1. - depth * (std::f64::consts::TAU * freq * t).cos());
-                } else {
-                    return s.value(_t);
-                }
-            }
-            _t -= s.duration;
+        let _t = match &self.sustain {
+            Some(sustain) => sustain.local_time(t),
+            None => t,
+        };
+        let i = self.segment_at(_t);
+        let Some(s) = self.segments.get(i) else {
+            return 0.;
+        };
+        let _t = _t - self.starts[i];
+        let value = if _t >= s.duration {
+            0.
+        } else if let Some((depth, freq)) = self.vibrato {
+            s.value(_t) * (1. - depth * (std::f64::consts::TAU * freq * t).cos())
+        } else {
+            s.value(_t)
+        };
+        match &self.modulation {
+            Some(param) => value * param.value(t),
+            None => value,
         }
-        0.
     }
 }
 
 impl crate::traits::Duration for Envelope {
     fn duration(&self) -> f64 {
-        if self.segments.is_empty() {
-            return 0.;
+        let total = self.total_duration();
+        match &self.sustain {
+            Some(sustain) => match sustain.recorded() {
+                Some(released) => released + total - sustain.looped(released),
+                None => f64::INFINITY,
+            },
+            None => total,
+        }
+    }
+
+    fn latch_release(&self, t: f64) {
+        if let Some(sustain) = &self.sustain {
+            sustain.latch(t);
         }
-        self.segments.iter().map(|s| s.duration).sum()
     }
 }
@@ -1,11 +1,19 @@
 pub mod bit_crush;
+pub mod control;
+pub mod convert;
+pub mod effect;
 pub mod envelope;
 pub mod noise;
 pub mod passband;
+pub mod resample;
+pub mod sampler;
 pub mod synth;
 pub mod traits;
 pub mod waveform;
 
+#[cfg(feature = "playback")]
+pub mod playback;
+
 #[cfg(feature = "serde")]
 pub mod serde;
 
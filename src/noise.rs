@@ -1,275 +1,318 @@
-use std::f64::consts::TAU;
-
 use crate::envelope::Envelope;
 use crate::lerp;
-use crate::traits::{Duration, Proc, ProcState};
+use crate::traits::ProcState;
+use crate::waveform::{Arpeggio, Phase};
 use rand::{
     distributions::{DistIter, Uniform},
     prelude::*,
     rngs::OsRng,
 };
 
-pub struct Noise<W, E>
-where
-    W: ProcState,
-    E: Proc + Duration,
-{
-    sample_rate: u32,
-    t: f64,
-    dt: f64,
-    duration: f64,
-    waveform: W,
-    envelope: E,
-}
-
-impl<W, E> Noise<W, E>
-where
-    W: ProcState,
-    E: Proc + Duration,
-{
-    pub fn new(sample_rate: u32, waveform: W, envelope: E) -> Option<Self> {
-        if sample_rate == 0 {
-            None
-        } else {
-            Some(Self {
-                sample_rate,
-                t: 0.,
-                dt: 1. / sample_rate as f64,
-                duration: envelope.duration(),
-                waveform,
-                envelope,
-            })
-        }
-    }
-
-    pub fn render_64<T>(self) -> crate::Samples<T>
-    where
-        T: From<f64>,
-    {
-        crate::Samples::<T> {
-            sample_rate: self.sample_rate,
-            samples: self.map(|s| s.into()).collect(),
-        }
-    }
-
-    pub fn render_32<T>(self) -> crate::Samples<T>
-    where
-        T: From<f32>,
-    {
-        crate::Samples::<T> {
-            sample_rate: self.sample_rate,
-            samples: self.map(|s| (s as f32).into()).collect(),
-        }
-    }
-
-    pub fn render_16<T>(self) -> crate::Samples<T>
-    where
-        T: From<i16>,
-    {
-        crate::Samples::<T> {
-            sample_rate: self.sample_rate,
-            samples: self
-                .map(|s| ((s * i16::MAX as f64) as i16).into())
-                .collect(),
-        }
-    }
-}
+pub type Noise<W, E> = crate::synth::Synth<W, E>;
 
-impl<W, E> crate::traits::Synth for Noise<W, E>
-where
-    W: ProcState,
-    E: Proc + Duration,
-{
-}
+/// Upper bound on the values drawn per sample when the noise frequency exceeds the sample rate.
+const MAX_DRAWS_PER_SAMPLE: usize = 64;
 
-unsafe impl<W, E> Send for Noise<W, E>
-where
-    W: ProcState,
-    E: Proc + Duration,
-{
+/// Values to draw at this sample: noise draws `freq` new values per second. Above the sample
+/// rate several are drawn per sample, so that filtered colors and the LFSR keep evolving at
+/// the declared rate, while the output is at most white at the sample rate.
+#[inline]
+fn draws(phase: &Phase) -> usize {
+    phase.wraps().min(MAX_DRAWS_PER_SAMPLE)
 }
 
-impl<W, E> Duration for Noise<W, E>
-where
-    W: ProcState,
-    E: Proc + Duration,
-{
-    fn duration(&self) -> f64 {
-        self.duration
-    }
-}
+type Random = DistIter<Uniform<f64>, OsRng, f64>;
 
-impl<W, E> Iterator for Noise<W, E>
-where
-    W: ProcState,
-    E: Proc + Duration,
-{
-    type Item = f64;
+/// Shapes the uniform white values drawn by a [`Clock`] into a noise color.
+trait Color {
+    /// Whether the output moves linearly between draws, rather than holding each one.
+    const INTERPOLATED: bool = true;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if self.t >= self.duration {
-            return None;
-        }
-        let w = self.waveform.next_value(self.t);
-        let w = w * self.envelope.value(self.t);
-        self.t += self.dt;
-        Some(w)
-    }
+    fn draw(&mut self, rng: &mut Random) -> f64;
 }
 
-pub struct WhiteNoise {
-    // interpolated
-    rng: DistIter<Uniform<f64>, OsRng, f64>,
-    freq: Envelope,
-    prev_phase: f64,
+/// Draws values from `color` at the frequency of `phase`, shared by every noise generator.
+struct Clock<C> {
+    rng: Random,
+    phase: Phase,
     prev_random: f64,
     curr_random: f64,
-    // interpolate: bool,
+    color: C,
 }
-impl WhiteNoise {
-    pub fn new(freq: Envelope) -> Self {
+impl<C: Color> Clock<C> {
+    fn new(freq: Envelope, color: C) -> Self {
         Self {
             rng: new_random(),
-            freq,
-            prev_phase: 0.,
+            phase: Phase::new(freq),
             prev_random: 0.,
             curr_random: 0.,
+            color,
         }
     }
-    pub fn new_simple(freq: f64) -> Option<Self> {
-        if !freq.is_normal() || freq <= 0. {
-            None
-        } else {
-            let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
-            Some(Self::new(freq))
+    fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+        Self {
+            phase: self.phase.with_arpeggio(arpeggio),
+            ..self
         }
     }
 }
-impl ProcState for WhiteNoise {
+impl<C: Color> ProcState for Clock<C> {
     fn next_value(&mut self, t: f64) -> f64 {
-        let p = (TAU * self.freq.value(t) * t * 2.).fract();
-        if p < self.prev_phase {
+        let p = self.phase.advance(t);
+        for _ in 0..draws(&self.phase) {
             self.prev_random = self.curr_random;
-            self.curr_random = self.rng.next().unwrap_or(0.);
+            self.curr_random = self.color.draw(&mut self.rng);
+        }
+        if C::INTERPOLATED {
+            lerp(self.prev_random, self.curr_random, p)
+        } else {
+            self.curr_random
         }
-        self.prev_phase = p;
-        lerp(self.prev_random, self.curr_random, p)
     }
 }
 
-pub struct PinkNoise {
-    // interpolated
-    rng: DistIter<Uniform<f64>, OsRng, f64>,
-    freq: Envelope,
-    prev_phase: f64,
-    prev_random: f64,
-    curr_random: f64,
-    b: [f64; 7],
-    // interpolate: bool,
+/// Constant frequency envelope for the `new_simple` constructors.
+fn constant(freq: f64) -> Option<Envelope> {
+    if !freq.is_normal() || freq <= 0. {
+        None
+    } else {
+        Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None)
+    }
 }
-impl PinkNoise {
-    pub fn new(freq: Envelope) -> Self {
-        Self {
-            rng: new_random(),
-            freq,
-            prev_phase: 0.,
-            prev_random: 0.,
-            curr_random: 0.,
-            b: [0.; 7],
+
+macro_rules! noise {
+    ($(#[$attr:meta])* $name:ident($color:ty)) => {
+        $(#[$attr])*
+        pub struct $name(Clock<$color>);
+        impl $name {
+            pub fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+                Self(self.0.with_arpeggio(arpeggio))
+            }
+        }
+        impl ProcState for $name {
+            fn next_value(&mut self, t: f64) -> f64 {
+                self.0.next_value(t)
+            }
         }
+    };
+}
+
+struct White;
+impl Color for White {
+    fn draw(&mut self, rng: &mut Random) -> f64 {
+        rng.next().unwrap_or(0.)
+    }
+}
+
+noise!(WhiteNoise(White));
+impl WhiteNoise {
+    pub fn new(freq: Envelope) -> Self {
+        Self(Clock::new(freq, White))
     }
     pub fn new_simple(freq: f64) -> Option<Self> {
-        if !freq.is_normal() || freq <= 0. {
-            None
-        } else {
-            let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
-            Some(Self::new(freq))
-        }
+        constant(freq).map(Self::new)
     }
 }
-impl ProcState for PinkNoise {
-    fn next_value(&mut self, t: f64) -> f64 {
-        let p = (TAU * self.freq.value(t) * t * 2.).fract();
-        if p < self.prev_phase {
-            self.prev_random = self.curr_random;
-            let white = self.rng.next().unwrap_or(0.);
-            self.b[0] = 0.99886 * self.b[0] + white * 0.0555179;
-            self.b[1] = 0.99332 * self.b[1] + white * 0.0750759;
-            self.b[2] = 0.96900 * self.b[2] + white * 0.1538520;
-            self.b[3] = 0.86650 * self.b[3] + white * 0.3104856;
-            self.b[4] = 0.55000 * self.b[4] + white * 0.5329522;
-            self.b[5] = -0.7616 * self.b[5] + white * 0.0168980;
-            self.curr_random = (self.b[0]
-                + self.b[1]
-                + self.b[2]
-                + self.b[3]
-                + self.b[4]
-                + self.b[5]
-                + self.b[6]
-                + white * 0.5362)
-                / 7.;
-            self.b[6] = white * 0.115926;
-        }
-        self.prev_phase = p;
-        lerp(self.prev_random, self.curr_random, p)
+
+/// Paul Kellet's pink noise filter, -3 dB per octave.
+#[derive(Default)]
+struct PinkFilter {
+    b: [f64; 7],
+}
+impl PinkFilter {
+    fn filter(&mut self, white: f64) -> f64 {
+        self.b[0] = 0.99886 * self.b[0] + white * 0.0555179;
+        self.b[1] = 0.99332 * self.b[1] + white * 0.0750759;
+        self.b[2] = 0.96900 * self.b[2] + white * 0.1538520;
+        self.b[3] = 0.86650 * self.b[3] + white * 0.3104856;
+        self.b[4] = 0.55000 * self.b[4] + white * 0.5329522;
+        self.b[5] = -0.7616 * self.b[5] + white * 0.0168980;
+        let pink = (self.b[0]
+            + self.b[1]
+            + self.b[2]
+            + self.b[3]
+            + self.b[4]
+            + self.b[5]
+            + self.b[6]
+            + white * 0.5362)
+            / 7.;
+        self.b[6] = white * 0.115926;
+        pink
     }
 }
 
-pub struct BrownNoise {
-    // interpolated
-    rng: DistIter<Uniform<f64>, OsRng, f64>,
-    freq: Envelope,
-    prev_phase: f64,
-    prev_random: f64,
-    curr_random: f64,
+impl Color for PinkFilter {
+    fn draw(&mut self, rng: &mut Random) -> f64 {
+        self.filter(rng.next().unwrap_or(0.))
+    }
+}
+
+noise!(PinkNoise(PinkFilter));
+impl PinkNoise {
+    pub fn new(freq: Envelope) -> Self {
+        Self(Clock::new(freq, PinkFilter::default()))
+    }
+    pub fn new_simple(freq: f64) -> Option<Self> {
+        constant(freq).map(Self::new)
+    }
+}
+
+/// Integrated white noise, kept within [-1, 1].
+struct Brown {
     rolloff: f64,
-    // interpolate: bool,
+    value: f64,
+}
+impl Color for Brown {
+    fn draw(&mut self, rng: &mut Random) -> f64 {
+        let white = rng.next().unwrap_or(0.);
+        self.value = (self.value + self.rolloff * white).clamp(-1., 1.);
+        self.value
+    }
 }
+
+noise!(BrownNoise(Brown));
 impl BrownNoise {
+    /// Rolloff of [`BrownNoise::default`].
+    pub const DEFAULT_ROLLOFF: f64 = 0.15;
+    /// Rolloff of [`BrownNoise::default_simple`], which descriptions are built with.
+    pub const SIMPLE_ROLLOFF: f64 = 0.1;
+
     pub fn default(freq: Envelope) -> Self {
-        Self {
-            rng: new_random(),
+        Self(Clock::new(
             freq,
-            prev_phase: 0.,
-            prev_random: 0.,
-            curr_random: 0.,
-            rolloff: 0.15,
-        }
+            Brown {
+                rolloff: Self::DEFAULT_ROLLOFF,
+                value: 0.,
+            },
+        ))
     }
-    pub fn new_simple(freq: f64, rolloff: f64) -> Option<Self> {
-        if !freq.is_normal() || freq <= 0. || !rolloff.is_normal() || rolloff <= 0. || rolloff >= 1.
-        {
+    pub fn new(freq: Envelope, rolloff: f64) -> Option<Self> {
+        if !rolloff.is_normal() || rolloff <= 0. || rolloff >= 1. {
             None
         } else {
-            let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
-            Some(Self {
-                rng: new_random(),
-                freq,
-                prev_phase: 0.,
-                prev_random: 0.,
-                curr_random: 0.,
-                rolloff,
-            })
+            Some(Self(Clock::new(freq, Brown { rolloff, value: 0. })))
         }
     }
+    pub fn new_simple(freq: f64, rolloff: f64) -> Option<Self> {
+        Self::new(constant(freq)?, rolloff)
+    }
     pub fn default_simple(freq: f64) -> Option<Self> {
-        Self::new_simple(freq, 0.1)
+        Self::new_simple(freq, Self::SIMPLE_ROLLOFF)
     }
 }
-impl ProcState for BrownNoise {
-    fn next_value(&mut self, t: f64) -> f64 {
-        let p = (TAU * self.freq.value(t) * t * 2.).fract();
-        if p < self.prev_phase {
-            self.prev_random = self.curr_random;
-            let white = self.rng.next().unwrap_or(0.);
-            self.curr_random = (self.curr_random + self.rolloff * white).clamp(-1., 1.);
+
+/// Reciprocal of the L1 norm (0.48028) of the impulse response of [`PinkFilter`] followed by
+/// a first difference: the largest output white values in [-1, 1] can produce, so blue noise
+/// reaches full scale without clipping.
+const BLUE_GAIN: f64 = 2.082;
+
+struct Blue {
+    pink: PinkFilter,
+    prev_pink: f64,
+}
+impl Color for Blue {
+    fn draw(&mut self, rng: &mut Random) -> f64 {
+        let pink = self.pink.filter(rng.next().unwrap_or(0.));
+        let blue = BLUE_GAIN * (pink - self.prev_pink);
+        self.prev_pink = pink;
+        blue
+    }
+}
+
+noise!(
+    /// Differentiated pink noise, +3 dB per octave.
+    BlueNoise(Blue)
+);
+impl BlueNoise {
+    pub fn new(freq: Envelope) -> Self {
+        Self(Clock::new(
+            freq,
+            Blue {
+                pink: PinkFilter::default(),
+                prev_pink: 0.,
+            },
+        ))
+    }
+    pub fn new_simple(freq: f64) -> Option<Self> {
+        constant(freq).map(Self::new)
+    }
+}
+
+/// Halved, so that it stays within [-1, 1].
+struct Violet {
+    prev_white: f64,
+}
+impl Color for Violet {
+    fn draw(&mut self, rng: &mut Random) -> f64 {
+        let white = rng.next().unwrap_or(0.);
+        let violet = (white - self.prev_white) / 2.;
+        self.prev_white = white;
+        violet
+    }
+}
+
+noise!(
+    /// Differentiated white noise, +6 dB per octave.
+    VioletNoise(Violet)
+);
+impl VioletNoise {
+    pub fn new(freq: Envelope) -> Self {
+        Self(Clock::new(freq, Violet { prev_white: 0. }))
+    }
+    pub fn new_simple(freq: f64) -> Option<Self> {
+        constant(freq).map(Self::new)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum LfsrMode {
+    /// 32767-step sequence, hiss-like.
+    #[default]
+    Long,
+    /// 93-step sequence, metallic and tonal.
+    Short,
+}
+
+/// 15-bit linear feedback shift register.
+struct Lfsr {
+    register: u16,
+    mode: LfsrMode,
+}
+impl Color for Lfsr {
+    const INTERPOLATED: bool = false;
+
+    fn draw(&mut self, _: &mut Random) -> f64 {
+        let tap = match self.mode {
+            LfsrMode::Long => 1,
+            LfsrMode::Short => 6,
+        };
+        let feedback = (self.register ^ (self.register >> tap)) & 1;
+        self.register = (self.register >> 1) | (feedback << 14);
+        if self.register & 1 == 0 {
+            1.
+        } else {
+            -1.
         }
-        self.prev_phase = p;
-        lerp(self.prev_random, self.curr_random, p)
+    }
+}
+
+noise!(
+    /// 15-bit linear feedback shift register noise, as in the NES and Game Boy noise channels.
+    /// Clocked `freq` times per second, and not interpolated.
+    LfsrNoise(Lfsr)
+);
+impl LfsrNoise {
+    pub fn new(freq: Envelope, mode: LfsrMode) -> Self {
+        Self(Clock::new(freq, Lfsr { register: 1, mode }))
+    }
+    pub fn new_simple(freq: f64, mode: LfsrMode) -> Option<Self> {
+        Some(Self::new(constant(freq)?, mode))
     }
 }
 
 #[inline]
-fn new_random() -> DistIter<Uniform<f64>, OsRng, f64> {
+fn new_random() -> Random {
     OsRng.sample_iter(rand::distributions::Uniform::new(-1., 1.))
 }
@@ -7,7 +7,7 @@ pub trait Filterable {
 
 fn filter<T>(
     planner: &mut RealFftPlanner<T>,
-    samples: &mut Vec<T>,
+    samples: &mut [T],
     sample_rate: f64,
     cutoff_freq: f64,
     high_pass: bool,
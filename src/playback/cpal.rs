@@ -0,0 +1,71 @@
+use std::sync::{Arc, Mutex};
+
+use ::cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use crate::playback::{Backend, Mixer};
+
+pub struct CpalBackend {
+    device: ::cpal::Device,
+    config: ::cpal::StreamConfig,
+    sample_format: ::cpal::SampleFormat,
+    stream: Option<::cpal::Stream>,
+}
+
+impl CpalBackend {
+    pub fn new(device: ::cpal::Device) -> Result<Self, String> {
+        let config = device
+            .default_output_config()
+            .map_err(|err| err.to_string())?;
+        Ok(Self {
+            device,
+            sample_format: config.sample_format(),
+            config: config.into(),
+            stream: None,
+        })
+    }
+
+    pub fn default_output() -> Result<Self, String> {
+        let device = ::cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| "No output device found".to_string())?;
+        Self::new(device)
+    }
+
+    fn build<T>(&self, mixer: Arc<Mutex<Mixer>>) -> Result<::cpal::Stream, String>
+    where
+        T: ::cpal::Sample,
+    {
+        self.device
+            .build_output_stream(
+                &self.config,
+                move |data: &mut [T], _: &::cpal::OutputCallbackInfo| {
+                    if let Ok(mut mixer) = mixer.lock() {
+                        mixer.fill(data, |v| ::cpal::Sample::from::<f32>(&(v as f32)));
+                    }
+                },
+                |err| eprintln!("an error occurred on stream: {}", err),
+            )
+            .map_err(|err| err.to_string())
+    }
+}
+
+impl Backend for CpalBackend {
+    fn sample_rate(&self) -> u32 {
+        self.config.sample_rate.0
+    }
+
+    fn channels(&self) -> usize {
+        self.config.channels as usize
+    }
+
+    fn start(&mut self, mixer: Arc<Mutex<Mixer>>) -> Result<(), String> {
+        let stream = match self.sample_format {
+            ::cpal::SampleFormat::I16 => self.build::<i16>(mixer),
+            ::cpal::SampleFormat::U16 => self.build::<u16>(mixer),
+            ::cpal::SampleFormat::F32 => self.build::<f32>(mixer),
+        }?;
+        stream.play().map_err(|err| err.to_string())?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+}
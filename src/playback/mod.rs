@@ -0,0 +1,220 @@
+#[cfg(feature = "cpal")]
+pub mod cpal;
+
+use std::sync::{Arc, Condvar, Mutex};
+
+use crate::{resample::Resampled, traits::Synth};
+
+pub trait Backend {
+    fn sample_rate(&self) -> u32;
+    fn channels(&self) -> usize;
+    fn start(&mut self, mixer: Arc<Mutex<Mixer>>) -> Result<(), String>;
+}
+
+#[derive(Clone)]
+pub struct Playback {
+    state: Arc<(Mutex<bool>, Condvar)>,
+}
+
+impl Playback {
+    fn new() -> Self {
+        Self {
+            state: Arc::new((Mutex::new(false), Condvar::new())),
+        }
+    }
+
+    fn finish(&self) {
+        let (finished, condvar) = &*self.state;
+        if let Ok(mut finished) = finished.lock() {
+            *finished = true;
+        }
+        condvar.notify_all();
+    }
+
+    /// Stops the sound; it is dropped from the mix before the next sample.
+    pub fn stop(&self) {
+        self.finish();
+    }
+
+    /// Whether the sound has played out, been stopped, or its player dropped.
+    pub fn is_finished(&self) -> bool {
+        self.state.0.lock().map(|f| *f).unwrap_or(true)
+    }
+
+    pub fn wait(&self) {
+        let (finished, condvar) = &*self.state;
+        if let Ok(finished) = finished.lock() {
+            drop(condvar.wait_while(finished, |f| !*f));
+        }
+    }
+
+    /// Returns `false` if the sound is still playing after `timeout`.
+    pub fn wait_timeout(&self, timeout: std::time::Duration) -> bool {
+        let (finished, condvar) = &*self.state;
+        match finished.lock() {
+            Ok(finished) => match condvar.wait_timeout_while(finished, timeout, |f| !*f) {
+                Ok((finished, _)) => *finished,
+                Err(_) => true,
+            },
+            Err(_) => true,
+        }
+    }
+}
+
+struct Voice {
+    sound: Box<dyn Synth>,
+    playback: Playback,
+}
+
+impl Drop for Voice {
+    fn drop(&mut self) {
+        self.playback.finish();
+    }
+}
+
+pub struct Mixer {
+    channels: usize,
+    voices: Vec<Voice>,
+}
+
+impl Mixer {
+    fn new(channels: usize) -> Self {
+        Self {
+            channels: channels.max(1),
+            voices: vec![],
+        }
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.voices.is_empty()
+    }
+
+    /// Mixes all playing sounds into interleaved `data`, duplicating each value across channels.
+    pub fn fill<T>(&mut self, data: &mut [T], convert: impl Fn(f64) -> T)
+    where
+        T: Copy,
+    {
+        for frame in data.chunks_mut(self.channels) {
+            let mut value = 0.;
+            self.voices.retain_mut(|voice| {
+                if voice.playback.is_finished() {
+                    return false;
+                }
+                match voice.sound.next() {
+                    Some(sample) => {
+                        value += sample;
+                        true
+                    }
+                    None => false,
+                }
+            });
+            frame.fill(convert(value.clamp(-1., 1.)));
+        }
+    }
+}
+
+pub struct Player<B>
+where
+    B: Backend,
+{
+    backend: B,
+    mixer: Arc<Mutex<Mixer>>,
+}
+
+impl<B> Player<B>
+where
+    B: Backend,
+{
+    pub fn new(mut backend: B) -> Result<Self, String> {
+        let mixer = Arc::new(Mutex::new(Mixer::new(backend.channels())));
+        backend.start(mixer.clone())?;
+        Ok(Self { backend, mixer })
+    }
+
+    pub fn backend(&self) -> &B {
+        &self.backend
+    }
+
+    /// Fails if `sound` cannot be resampled to the backend's sample rate.
+    pub fn play<S>(&self, sound: S) -> Result<Playback, String>
+    where
+        S: Synth + 'static,
+    {
+        let sample_rate = self.backend.sample_rate();
+        let sound: Box<dyn Synth> =
+            if sound.sample_rate() == sample_rate {
+                Box::new(sound)
+            } else {
+                let from = sound.sample_rate();
+                Box::new(Resampled::new(sound, sample_rate).ok_or_else(|| {
+                    format!("Cannot resample from {} Hz to {} Hz", from, sample_rate)
+                })?)
+            };
+        let playback = Playback::new();
+        if let Ok(mut mixer) = self.mixer.lock() {
+            mixer.voices.push(Voice {
+                sound,
+                playback: playback.clone(),
+            });
+        } else {
+            playback.finish();
+        }
+        Ok(playback)
+    }
+
+    /// Stops every sound playing.
+    pub fn stop_all(&self) {
+        if let Ok(mut mixer) = self.mixer.lock() {
+            mixer.voices.clear();
+        }
+    }
+}
+
+impl<B> Drop for Player<B>
+where
+    B: Backend,
+{
+    fn drop(&mut self) {
+        self.stop_all();
+    }
+}
+
+pub struct MemorySink {
+    sample_rate: u32,
+    channels: usize,
+    mixer: Option<Arc<Mutex<Mixer>>>,
+}
+
+impl MemorySink {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        Self {
+            sample_rate,
+            channels,
+            mixer: None,
+        }
+    }
+
+    /// Renders `frames` interleaved frames, as an output device callback would.
+    pub fn pull(&self, frames: usize) -> Vec<f32> {
+        let mut data = vec![0.; frames * self.channels];
+        if let Some(mut mixer) = self.mixer.as_ref().and_then(|m| m.lock().ok()) {
+            mixer.fill(&mut data, |v| v as f32);
+        }
+        data
+    }
+}
+
+impl Backend for MemorySink {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn channels(&self) -> usize {
+        self.channels
+    }
+
+    fn start(&mut self, mixer: Arc<Mutex<Mixer>>) -> Result<(), String> {
+        self.mixer = Some(mixer);
+        Ok(())
+    }
+}
@@ -0,0 +1,260 @@
+use std::collections::VecDeque;
+use std::f64::consts::{PI, TAU};
+
+use crate::traits::{Duration, SampleRate, Synth};
+
+pub trait Resamplable {
+    fn resample(self, sample_rate: u32) -> Self;
+}
+
+/// Blackman-windowed sinc low-pass kernel of `2 * half_len + 1` taps, normalized to unity gain.
+/// `cutoff` is a fraction of the sample rate.
+pub(crate) fn sinc_kernel(cutoff: f64, half_len: usize) -> Vec<f64> {
+    let len = 2 * half_len + 1;
+    let mut kernel: Vec<f64> = (0..len)
+        .map(|k| {
+            let x = k as f64 - half_len as f64;
+            let window = 0.42 - 0.5 * (TAU * k as f64 / (len - 1) as f64).cos()
+                + 0.08 * (2. * TAU * k as f64 / (len - 1) as f64).cos();
+            2. * cutoff * sinc(2. * cutoff * x) * window
+        })
+        .collect();
+    let sum: f64 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|h| *h /= sum);
+    kernel
+}
+
+#[inline]
+fn blackman(x: f64) -> f64 {
+    if x.abs() >= 1. {
+        0.
+    } else {
+        0.42 + 0.5 * (PI * x).cos() + 0.08 * (TAU * x).cos()
+    }
+}
+
+#[inline]
+pub(crate) fn sinc(x: f64) -> f64 {
+    if x == 0. {
+        1.
+    } else {
+        (PI * x).sin() / (PI * x)
+    }
+}
+
+pub(crate) struct Decimator {
+    factor: usize,
+    kernel: Vec<f64>,
+    history: VecDeque<f64>,
+}
+
+impl Decimator {
+    const TAPS_PER_PHASE: usize = 16;
+
+    pub(crate) fn new(factor: usize) -> Self {
+        let kernel = sinc_kernel(0.45 / factor as f64, Self::TAPS_PER_PHASE * factor);
+        Self {
+            factor,
+            history: VecDeque::from(vec![0.; kernel.len()]),
+            kernel,
+        }
+    }
+
+    pub(crate) fn factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Number of input samples to push before the first output, so that the
+    /// filter's group delay is compensated.
+    pub(crate) fn priming(&self) -> usize {
+        self.kernel.len() / 2 + 1 - self.factor
+    }
+
+    pub(crate) fn push(&mut self, sample: f64) {
+        self.history.pop_front();
+        self.history.push_back(sample);
+    }
+
+    pub(crate) fn output(&self) -> f64 {
+        self.kernel
+            .iter()
+            .zip(self.history.iter().rev())
+            .map(|(h, x)| h * x)
+            .sum()
+    }
+}
+
+/// Band-limited interpolation between sample rates `from` and `to`.
+struct Interpolator {
+    step: f64,
+    cutoff: f64,
+    half_width: f64,
+}
+
+impl Interpolator {
+    const ZERO_CROSSINGS: f64 = 16.;
+
+    fn new(from: u32, to: u32) -> Self {
+        let ratio = (to as f64 / from as f64).min(1.);
+        Self {
+            step: from as f64 / to as f64,
+            cutoff: 0.475 * ratio,
+            half_width: Self::ZERO_CROSSINGS / ratio,
+        }
+    }
+
+    /// Input sample indices contributing to the output at input position `x`.
+    fn support(&self, x: f64) -> (i64, i64) {
+        (
+            (x - self.half_width).ceil() as i64,
+            (x + self.half_width).floor() as i64,
+        )
+    }
+
+    fn value(&self, x: f64, sample: impl Fn(i64) -> f64) -> f64 {
+        let (first, last) = self.support(x);
+        (first..=last)
+            .map(|k| {
+                let d = x - k as f64;
+                sample(k)
+                    * 2.
+                    * self.cutoff
+                    * sinc(2. * self.cutoff * d)
+                    * blackman(d / self.half_width)
+            })
+            .sum()
+    }
+}
+
+fn resample(samples: &[f64], from: u32, to: u32) -> Vec<f64> {
+    let interpolator = Interpolator::new(from, to);
+    let len = (samples.len() as f64 / interpolator.step).round() as usize;
+    (0..len)
+        .map(|m| {
+            interpolator.value(m as f64 * interpolator.step, |k| {
+                usize::try_from(k)
+                    .ok()
+                    .and_then(|k| samples.get(k))
+                    .copied()
+                    .unwrap_or(0.)
+            })
+        })
+        .collect()
+}
+
+impl Resamplable for crate::Samples<f64> {
+    fn resample(self, sample_rate: u32) -> Self {
+        if sample_rate == 0 || sample_rate == self.sample_rate {
+            return self;
+        }
+        Self {
+            samples: resample(&self.samples, self.sample_rate, sample_rate),
+            sample_rate,
+        }
+    }
+}
+
+impl Resamplable for crate::Samples<f32> {
+    fn resample(self, sample_rate: u32) -> Self {
+        if sample_rate == 0 || sample_rate == self.sample_rate {
+            return self;
+        }
+        let samples: Vec<f64> = self.samples.into_iter().map(f64::from).collect();
+        Self {
+            samples: resample(&samples, self.sample_rate, sample_rate)
+                .into_iter()
+                .map(|s| s as f32)
+                .collect(),
+            sample_rate,
+        }
+    }
+}
+
+pub struct Resampled<S>
+where
+    S: Synth,
+{
+    sound: S,
+    sample_rate: u32,
+    interpolator: Interpolator,
+    buffer: VecDeque<f64>,
+    buffer_start: i64,
+    index: usize,
+    t: f64,
+    dt: f64,
+}
+
+impl<S> Resampled<S>
+where
+    S: Synth,
+{
+    pub fn new(sound: S, sample_rate: u32) -> Option<Self> {
+        if sample_rate == 0 {
+            None
+        } else {
+            Some(Self {
+                interpolator: Interpolator::new(sound.sample_rate(), sample_rate),
+                sound,
+                sample_rate,
+                buffer: VecDeque::new(),
+                buffer_start: 0,
+                index: 0,
+                t: 0.,
+                dt: 1. / sample_rate as f64,
+            })
+        }
+    }
+}
+
+impl<S> Synth for Resampled<S> where S: Synth {}
+
+impl<S> Duration for Resampled<S>
+where
+    S: Synth,
+{
+    fn duration(&self) -> f64 {
+        self.sound.duration()
+    }
+}
+
+impl<S> SampleRate for Resampled<S>
+where
+    S: Synth,
+{
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+}
+
+impl<S> Iterator for Resampled<S>
+where
+    S: Synth,
+{
+    type Item = f64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.t >= self.duration() {
+            return None;
+        }
+        let position = self.index as f64 * self.interpolator.step;
+        let (first, last) = self.interpolator.support(position);
+        while self.buffer_start < first && !self.buffer.is_empty() {
+            self.buffer.pop_front();
+            self.buffer_start += 1;
+        }
+        while self.buffer_start + (self.buffer.len() as i64) <= last {
+            self.buffer.push_back(self.sound.next().unwrap_or(0.));
+        }
+        let (buffer, start) = (&self.buffer, self.buffer_start);
+        let value = self.interpolator.value(position, |k| {
+            usize::try_from(k - start)
+                .ok()
+                .and_then(|k| buffer.get(k))
+                .copied()
+                .unwrap_or(0.)
+        });
+        self.index += 1;
+        self.t += self.dt;
+        Some(value)
+    }
+}
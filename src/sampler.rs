@@ -0,0 +1,166 @@
+use crate::{
+    envelope::Envelope,
+    traits::{Proc, ProcState},
+    Samples,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Looping {
+    /// Plays once, then stays silent.
+    #[default]
+    None,
+    /// Jumps back to `start` on reaching `end`; both are sample indices.
+    Forward { start: usize, end: usize },
+    /// Plays back and forth between `start` and the last sample before `end`.
+    PingPong { start: usize, end: usize },
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    Nearest,
+    #[default]
+    Linear,
+    /// Catmull-Rom spline through the four nearest samples.
+    Cubic,
+}
+
+/// Plays recorded samples, at `freq / root` times their original speed.
+pub struct Sampler {
+    samples: Samples<f64>,
+    root: f64,
+    freq: Envelope,
+    looping: Looping,
+    interpolation: Interpolation,
+    t: f64,
+    position: f64,
+}
+
+impl Sampler {
+    /// `root` is the pitch of the recording, in the unit of `freq`.
+    pub fn new(samples: Samples<f64>, root: f64, freq: Envelope) -> Option<Self> {
+        if samples.sample_rate == 0 || !root.is_normal() || root <= 0. {
+            None
+        } else {
+            Some(Self {
+                samples,
+                root,
+                freq,
+                looping: Looping::None,
+                interpolation: Interpolation::Linear,
+                t: 0.,
+                position: 0.,
+            })
+        }
+    }
+
+    /// Plays at the original pitch.
+    pub fn new_simple(samples: Samples<f64>) -> Option<Self> {
+        let freq = Envelope::from_duration(1., 0., f64::INFINITY, 0., 0., None).unwrap();
+        Self::new(samples, 1., freq)
+    }
+
+    pub fn with_looping(self, looping: Looping) -> Option<Self> {
+        let len = self.samples.samples.len();
+        match looping {
+            Looping::Forward { start, end } if start >= end || end > len => None,
+            Looping::PingPong { start, end } if start + 1 >= end || end > len => None,
+            _ => Some(Self { looping, ..self }),
+        }
+    }
+
+    pub fn with_interpolation(self, interpolation: Interpolation) -> Self {
+        Self {
+            interpolation,
+            ..self
+        }
+    }
+
+    /// Maps a position along the playback onto the recording.
+    fn locate(&self, position: f64) -> f64 {
+        match self.looping {
+            Looping::None => position,
+            Looping::Forward { start, end } => {
+                let (start, end) = (start as f64, end as f64);
+                if position < end {
+                    position
+                } else {
+                    start + (position - start) % (end - start)
+                }
+            }
+            Looping::PingPong { start, end } => {
+                let (start, last) = (start as f64, (end - 1) as f64);
+                let len = last - start;
+                if position <= last {
+                    position
+                } else {
+                    let q = (position - last) % (2. * len);
+                    if q < len {
+                        last - q
+                    } else {
+                        start + q - len
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moves a looping position back by whole loop periods, keeping it within one period
+    /// past the loop so that it does not lose precision as playback goes on.
+    fn wrap(&self, position: f64) -> f64 {
+        let (last, period) = match self.looping {
+            Looping::None => return position,
+            Looping::Forward { start, end } => (end as f64, (end - start) as f64),
+            Looping::PingPong { start, end } => ((end - 1) as f64, 2. * (end - 1 - start) as f64),
+        };
+        if position < last + period {
+            position
+        } else {
+            last + (position - last) % period
+        }
+    }
+
+    /// Sample `i` samples along the playback, silent outside the recording.
+    fn sample(&self, i: f64) -> f64 {
+        if i < 0. {
+            return 0.;
+        }
+        self.samples
+            .samples
+            .get(self.locate(i) as usize)
+            .copied()
+            .unwrap_or(0.)
+    }
+}
+
+impl ProcState for Sampler {
+    fn next_value(&mut self, t: f64) -> f64 {
+        let dt = t - self.t;
+        if dt > 0. {
+            let speed = self.freq.value(self.t) / self.root;
+            self.position = self.wrap(self.position + speed * self.samples.sample_rate as f64 * dt);
+        }
+        self.t = t;
+
+        let position = self.position;
+        let i = position.floor();
+        let frac = position - i;
+        match self.interpolation {
+            Interpolation::Nearest => self.sample(position.round()),
+            Interpolation::Linear => crate::lerp(self.sample(i), self.sample(i + 1.), frac),
+            Interpolation::Cubic => {
+                let (p0, p1, p2, p3) = (
+                    self.sample(i - 1.),
+                    self.sample(i),
+                    self.sample(i + 1.),
+                    self.sample(i + 2.),
+                );
+                p1 + 0.5
+                    * frac
+                    * (p2 - p0
+                        + frac
+                            * (2. * p0 - 5. * p1 + 4. * p2 - p3
+                                + frac * (3. * (p1 - p2) + p3 - p0)))
+            }
+        }
+    }
+}
@@ -5,10 +5,16 @@ use serde::{Deserialize, Serialize};
 use validator::{Validate, ValidationError, ValidationErrors};
 
 use crate::{
-    envelope::Envelope,
-    noise::{BrownNoise, Noise, PinkNoise, WhiteNoise},
+    control::{Control, Param},
+    effect::{filter::Biquad, waveshaper::Waveshaper, EffectChain},
+    envelope::{Curve, Envelope, EnvelopeError},
+    noise::{BlueNoise, BrownNoise, LfsrMode, LfsrNoise, PinkNoise, VioletNoise, WhiteNoise},
     synth::Synth,
-    waveform::{Breaker, Sawtooth, Sine, Square, Tangent, Triangle},
+    traits::Duration,
+    waveform::{
+        Antialiasing, Arpeggio, Breaker, Fm, RingMod, Sawtooth, Sine, Square, Tangent, Triangle,
+        Wavetable,
+    },
 };
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -16,96 +22,334 @@ use crate::{
 #[serde(rename_all = "lowercase")]
 pub enum WaveformType {
     Sine,
-    Triangle,
-    Sawtooth,
+    Triangle {
+        #[serde(default)]
+        antialiasing: Antialiasing,
+    },
+    Sawtooth {
+        #[serde(default)]
+        antialiasing: Antialiasing,
+    },
     Breaker,
     Tangent,
     #[serde(rename_all = "camelCase")]
     Square {
         square_duty: f64,
+        #[serde(default)]
+        antialiasing: Antialiasing,
     },
     WhiteNoise,
     PinkNoise,
     BrownNoise,
+    BlueNoise,
+    VioletNoise,
+    /// NES and Game Boy style periodic noise.
+    Lfsr {
+        #[serde(default)]
+        mode: LfsrMode,
+    },
+    /// One cycle of samples, stretched to the frequency.
+    Wavetable {
+        table: Vec<f64>,
+    },
+    /// Sine carrier phase-modulated by a sine at `ratio` times its frequency. The modulation
+    /// index sweeps linearly from `index` to `indexEnd` over the sound.
+    #[serde(rename_all = "camelCase")]
+    Fm {
+        ratio: f64,
+        index: f64,
+        #[serde(default)]
+        index_end: Option<f64>,
+    },
+    /// Sine at the frequency multiplied by a sine at `modulatorFrequency`.
+    #[serde(rename_all = "camelCase")]
+    RingMod {
+        modulator_frequency: f64,
+    },
 }
 
 impl Validate for WaveformType {
     fn validate(&self) -> Result<(), ValidationErrors> {
         match self {
-            WaveformType::Square { square_duty } => {
-                if *square_duty < 0. || *square_duty > 100. {
+            WaveformType::Square { square_duty, .. } => {
+                if *square_duty > 0. && *square_duty < 100. {
+                    Ok(())
+                } else {
+                    Err(Self::square_duty_errors())
+                }
+            }
+            WaveformType::Wavetable { table } => {
+                if table.is_empty() || table.iter().any(|v| !v.is_finite()) {
                     let mut errors = ValidationErrors::new();
                     errors.add(
-                        "square_duty",
-                        ValidationError::new(
-                            "'square_duty' must be a percentage value between 0 and 100",
-                        ),
+                        "table",
+                        ValidationError::new("'table' must be a non-empty list of finite values"),
                     );
                     Err(errors)
                 } else {
                     Ok(())
                 }
             }
+            WaveformType::Fm {
+                ratio,
+                index,
+                index_end,
+            } => {
+                let mut errors = ValidationErrors::new();
+                if !ratio.is_normal() || *ratio <= 0. {
+                    errors = Self::ratio_errors();
+                }
+                if [*index, index_end.unwrap_or(0.)]
+                    .iter()
+                    .any(|i| !i.is_finite() || *i < 0.)
+                {
+                    errors.add(
+                        "index",
+                        ValidationError::new("'index' and 'indexEnd' must be non-negative"),
+                    );
+                }
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors)
+                }
+            }
+            WaveformType::RingMod {
+                modulator_frequency,
+            } => {
+                if !modulator_frequency.is_normal() || *modulator_frequency <= 0. {
+                    Err(Self::modulator_frequency_errors())
+                } else {
+                    Ok(())
+                }
+            }
             _ => Ok(()),
         }
     }
 }
 
+macro_rules! with_arpeggio {
+    ($waveform:expr, $arpeggio:expr) => {
+        match $arpeggio {
+            Some(arpeggio) => $waveform.with_arpeggio(arpeggio),
+            None => $waveform,
+        }
+    };
+}
+
 impl WaveformType {
     pub fn build(
         self,
         sample_rate: u32,
+        oversampling: usize,
         frequency: f64,
+        arpeggio: Option<Arpeggio>,
         envelope: Envelope,
-    ) -> Box<dyn crate::traits::Synth> {
-        match self {
+    ) -> Result<Box<dyn crate::traits::Synth>, ValidationErrors> {
+        self.build_modulated(
+            sample_rate,
+            oversampling,
+            frequency,
+            arpeggio,
+            None,
+            envelope,
+        )
+    }
+
+    /// Like [`WaveformType::build`], with the frequency scaled by `pitch`.
+    pub fn build_modulated(
+        self,
+        sample_rate: u32,
+        oversampling: usize,
+        frequency: f64,
+        arpeggio: Option<Arpeggio>,
+        pitch: Option<Param>,
+        envelope: Envelope,
+    ) -> Result<Box<dyn crate::traits::Synth>, ValidationErrors> {
+        let constant = |frequency: f64| {
+            let freq = Envelope::from_duration(frequency, 0., f64::INFINITY, 0., 0., None)?;
+            Some(match &pitch {
+                Some(pitch) => freq.with_modulation(pitch.clone()),
+                None => freq,
+            })
+        };
+        let freq = constant(frequency).ok_or_else(Self::frequency_errors)?;
+        let synth: Box<dyn crate::traits::Synth> = match self {
             Self::Sine => {
-                let waveform = Sine::new_simple(frequency).unwrap();
-                let synth = Synth::new(sample_rate, waveform, envelope).unwrap();
+                let waveform = with_arpeggio!(Sine::new(freq), arpeggio);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
                 Box::new(synth)
             }
-            Self::Triangle => {
-                let waveform = Triangle::new_simple(frequency).unwrap();
-                let synth = Synth::new(sample_rate, waveform, envelope).unwrap();
+            Self::Triangle { antialiasing } => {
+                let waveform = with_arpeggio!(
+                    Triangle::new(freq).with_antialiasing(antialiasing),
+                    arpeggio
+                );
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
                 Box::new(synth)
             }
-            Self::Sawtooth => {
-                let waveform = Sawtooth::new_simple(frequency).unwrap();
-                let synth = Synth::new(sample_rate, waveform, envelope).unwrap();
+            Self::Sawtooth { antialiasing } => {
+                let waveform = with_arpeggio!(
+                    Sawtooth::new(freq).with_antialiasing(antialiasing),
+                    arpeggio
+                );
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
                 Box::new(synth)
             }
             Self::Breaker => {
-                let waveform = Breaker::new_simple(frequency).unwrap();
-                let synth = Synth::new(sample_rate, waveform, envelope).unwrap();
+                let waveform = with_arpeggio!(Breaker::new(freq), arpeggio);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
                 Box::new(synth)
             }
             Self::Tangent => {
-                let waveform = Tangent::default_simple(frequency).unwrap();
-                let synth = Synth::new(sample_rate, waveform, envelope).unwrap();
+                let waveform = with_arpeggio!(Tangent::default(freq), arpeggio);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
                 Box::new(synth)
             }
-            Self::Square { square_duty } => {
-                let waveform = Square::new_simple(frequency, square_duty).unwrap();
-                let synth = Synth::new(sample_rate, waveform, envelope).unwrap();
+            Self::Square {
+                square_duty,
+                antialiasing,
+            } => {
+                let square_duty = square_duty / 100.;
+                if !(square_duty > 0. && square_duty < 1.) {
+                    return Err(Self::square_duty_errors());
+                }
+                let square_duty =
+                    Envelope::from_duration(square_duty, 0., f64::INFINITY, 0., 0., None).unwrap();
+                let waveform = with_arpeggio!(
+                    Square::new(freq, square_duty).with_antialiasing(antialiasing),
+                    arpeggio
+                );
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
                 Box::new(synth)
             }
             Self::WhiteNoise => {
-                let waveform = WhiteNoise::new_simple(frequency).unwrap();
-                let synth = Noise::new(sample_rate, waveform, envelope).unwrap();
+                let waveform = with_arpeggio!(WhiteNoise::new(freq), arpeggio);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
                 Box::new(synth)
             }
             Self::PinkNoise => {
-                let waveform = PinkNoise::new_simple(frequency).unwrap();
-                let synth = Noise::new(sample_rate, waveform, envelope).unwrap();
+                let waveform = with_arpeggio!(PinkNoise::new(freq), arpeggio);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
                 Box::new(synth)
             }
             Self::BrownNoise => {
-                let waveform = BrownNoise::default_simple(frequency).unwrap();
-                let synth = Noise::new(sample_rate, waveform, envelope).unwrap();
+                let waveform = with_arpeggio!(
+                    BrownNoise::new(freq, BrownNoise::SIMPLE_ROLLOFF).unwrap(),
+                    arpeggio
+                );
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
                 Box::new(synth)
             }
-        }
+            Self::BlueNoise => {
+                let waveform = with_arpeggio!(BlueNoise::new(freq), arpeggio);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
+                Box::new(synth)
+            }
+            Self::VioletNoise => {
+                let waveform = with_arpeggio!(VioletNoise::new(freq), arpeggio);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
+                Box::new(synth)
+            }
+            Self::Lfsr { mode } => {
+                let waveform = with_arpeggio!(LfsrNoise::new(freq, mode), arpeggio);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
+                Box::new(synth)
+            }
+            Self::Wavetable { table } => {
+                let waveform = with_arpeggio!(Wavetable::new(freq, table).unwrap(), arpeggio);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
+                Box::new(synth)
+            }
+            Self::Fm {
+                ratio,
+                index,
+                index_end,
+            } => {
+                let ratio = Envelope::from_duration(ratio, 0., f64::INFINITY, 0., 0., None)
+                    .ok_or_else(Self::ratio_errors)?;
+                let index_end = index_end.unwrap_or(index);
+                let index = Envelope::from_points(
+                    vec![
+                        (0., index),
+                        (envelope.duration(), index_end),
+                        (f64::INFINITY, index_end),
+                    ],
+                    None,
+                )
+                .unwrap();
+                let waveform = with_arpeggio!(Fm::new(freq, ratio, index), arpeggio);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
+                Box::new(synth)
+            }
+            Self::RingMod {
+                modulator_frequency,
+            } => {
+                let carrier = with_arpeggio!(Sine::new(freq), arpeggio);
+                let modulator =
+                    constant(modulator_frequency).ok_or_else(Self::modulator_frequency_errors)?;
+                let modulator = Sine::new(modulator);
+                let waveform = RingMod::new(carrier, modulator);
+                let synth =
+                    Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
+                Box::new(synth)
+            }
+        };
+        Ok(synth)
+    }
+
+    fn errors(field: &'static str, message: &'static str) -> ValidationErrors {
+        let mut errors = ValidationErrors::new();
+        errors.add(field, ValidationError::new(message));
+        errors
+    }
+
+    fn square_duty_errors() -> ValidationErrors {
+        Self::errors(
+            "square_duty",
+            "'square_duty' must be a percentage value between 0 and 100",
+        )
+    }
+
+    fn frequency_errors() -> ValidationErrors {
+        Self::errors("frequency", "'frequency' must be positive")
     }
+
+    fn ratio_errors() -> ValidationErrors {
+        Self::errors("ratio", "'ratio' must be positive")
+    }
+
+    fn modulator_frequency_errors() -> ValidationErrors {
+        Self::errors(
+            "modulator_frequency",
+            "'modulatorFrequency' must be positive",
+        )
+    }
+}
+
+/// Shape of the amplitude envelope built from a [`Description`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvelopeType {
+    /// Attack, sustain with punch, then decay to silence.
+    #[default]
+    Jfxr,
+    /// Attack, decay to `sustainLevel`, sustain, then release to silence.
+    Adsr,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -122,6 +366,10 @@ pub struct Description {
     #[validate(range(min = 1))]
     pub sample_rate: u32,
 
+    #[serde(default = "Description::oversampling_default")]
+    #[validate(range(min = 1, max = 16))]
+    pub oversampling: usize,
+
     #[serde(default)]
     #[validate(range(min = 0.))]
     pub attack: f64,
@@ -138,13 +386,57 @@ pub struct Description {
     #[validate(range(min = 0., max = 100.))]
     pub sustain_punch: f64,
 
+    #[serde(default)]
+    pub envelope: EnvelopeType,
+
+    #[serde(default = "Description::sustain_level_default")]
+    #[validate(range(min = 0., max = 100.))]
+    pub sustain_level: f64,
+
+    #[serde(default)]
+    #[validate(range(min = 0.))]
+    pub release: f64,
+
+    #[serde(default)]
+    #[validate(custom(function = "Description::validate_curve"))]
+    pub attack_curve: Curve,
+
+    #[serde(default)]
+    #[validate(custom(function = "Description::validate_curve"))]
+    pub sustain_curve: Curve,
+
+    #[serde(default)]
+    #[validate(custom(function = "Description::validate_curve"))]
+    pub decay_curve: Curve,
+
+    #[serde(default)]
+    #[validate(custom(function = "Description::validate_curve"))]
+    pub release_curve: Curve,
+
     #[serde(default = "Description::amplification_default")]
     #[validate(range(min = 0.))]
     pub amplification: f64,
 
-    #[validate(range(min = 0.))]
+    #[validate(custom(function = "Description::validate_frequency"))]
     pub frequency: f64,
 
+    #[serde(default)]
+    #[validate(custom(function = "Description::validate_arpeggio"))]
+    pub arpeggio: Option<Arpeggio>,
+
+    #[serde(default)]
+    #[validate(custom(function = "Description::validate_distortion"))]
+    pub distortion: Option<Waveshaper>,
+
+    /// sfxr phaser offset, see [`crate::effect::phaser::Phaser::from_sfxr`].
+    #[serde(default)]
+    #[validate(range(min = -1., max = 1.))]
+    pub phaser_offset: f64,
+
+    #[serde(default)]
+    #[validate(range(min = -1., max = 1.))]
+    pub phaser_sweep: f64,
+
     #[serde(flatten)]
     #[validate]
     pub waveform: WaveformType,
@@ -152,31 +444,105 @@ pub struct Description {
 
 impl Description {
     pub fn build(self) -> Result<Box<dyn crate::traits::Synth>, ValidationErrors> {
+        self.build_with(None)
+    }
+
+    /// Like [`Description::build`], also returning a handle to change the frequency,
+    /// amplitude and low-pass cutoff of the sound while it plays, from any thread.
+    pub fn build_controlled(
+        self,
+    ) -> Result<(Box<dyn crate::traits::Synth>, Control), ValidationErrors> {
+        let control = Control::new(self.frequency, self.sample_rate as f64 / 2.);
+        let synth = self.build_with(Some(&control))?;
+        Ok((synth, control))
+    }
+
+    fn build_with(
+        self,
+        control: Option<&Control>,
+    ) -> Result<Box<dyn crate::traits::Synth>, ValidationErrors> {
         let mut errors = if let Err(errors) = self.validate() {
             errors
         } else {
             ValidationErrors::new()
         };
-        if self.attack + self.sustain + self.decay == 0. {
-            errors.add("duration", ValidationError::new("Sound duration must be positive; consider setting 'attack', 'sustain' and/or 'decay' values."));
+        let release = match self.envelope {
+            EnvelopeType::Jfxr => 0.,
+            EnvelopeType::Adsr => self.release,
+        };
+        if self.attack + self.sustain + self.decay + release == 0. {
+            errors.add("duration", ValidationError::new("Sound duration must be positive; consider setting 'attack', 'sustain', 'decay' and/or 'release' values."));
         }
         if !errors.is_empty() {
             return Err(errors);
         }
 
-        let envelope = Envelope::from_duration(
-            self.amplification,
-            self.attack,
-            self.sustain,
-            self.decay,
-            self.sustain_punch,
-            None,
-        )
-        .unwrap();
+        let envelope = match self.envelope {
+            EnvelopeType::Jfxr => Envelope::try_from_duration_with_curves(
+                self.amplification,
+                self.attack,
+                self.sustain,
+                self.decay,
+                self.sustain_punch,
+                (self.attack_curve, self.sustain_curve, self.decay_curve),
+                None,
+            ),
+            EnvelopeType::Adsr => Envelope::try_from_adsr_with_curves(
+                self.amplification,
+                self.attack,
+                self.decay,
+                self.sustain_level / 100.,
+                self.sustain,
+                self.release,
+                (
+                    self.attack_curve,
+                    self.decay_curve,
+                    self.sustain_curve,
+                    self.release_curve,
+                ),
+                None,
+            ),
+        }
+        .map_err(Self::envelope_errors)?;
+        let envelope = match control {
+            Some(control) => envelope.with_modulation(control.amplitude.clone()),
+            None => envelope,
+        };
 
-        Ok(self
-            .waveform
-            .build(self.sample_rate, self.frequency, envelope))
+        let synth = self.waveform.build_modulated(
+            self.sample_rate,
+            self.oversampling,
+            self.frequency,
+            self.arpeggio,
+            control.map(|control| control.frequency.clone()),
+            envelope,
+        )?;
+        let synth: Box<dyn crate::traits::Synth> = match self.distortion {
+            Some(waveshaper) => Box::new(synth.effect(waveshaper)),
+            None => synth,
+        };
+        let synth: Box<dyn crate::traits::Synth> =
+            if self.phaser_offset == 0. && self.phaser_sweep == 0. {
+                synth
+            } else {
+                Box::new(synth.sfxr_phaser(self.phaser_offset, self.phaser_sweep))
+            };
+        match control {
+            Some(control) => {
+                let filter = Biquad::low_pass(self.sample_rate, control.cutoff())
+                    .with_cutoff_control(control.cutoff.clone());
+                Ok(Box::new(synth.effect(filter)))
+            }
+            None => Ok(synth),
+        }
+    }
+
+    fn envelope_errors(error: EnvelopeError) -> ValidationErrors {
+        let mut validation_error = ValidationError::new("envelope");
+        validation_error.message = Some(error.to_string().into());
+        let mut errors = ValidationErrors::new();
+        errors.add("envelope", validation_error);
+        errors
     }
 
     #[inline]
@@ -191,11 +557,63 @@ impl Description {
         }
     }
 
+    #[inline]
+    fn validate_frequency(value: f64) -> Result<(), ValidationError> {
+        if value.is_normal() && value > 0. {
+            Ok(())
+        } else {
+            Err(ValidationError::new("'frequency' must be positive"))
+        }
+    }
+
+    #[inline]
+    fn validate_curve(value: &Curve) -> Result<(), ValidationError> {
+        if value.is_valid() {
+            Ok(())
+        } else {
+            Err(ValidationError::new(
+                "Curve power must be positive and Bezier control values finite",
+            ))
+        }
+    }
+
+    #[inline]
+    fn validate_arpeggio(value: &Arpeggio) -> Result<(), ValidationError> {
+        if value.is_valid() {
+            Ok(())
+        } else {
+            Err(ValidationError::new(
+                "Arpeggio interval must be positive and steps a non-empty list of finite semitones",
+            ))
+        }
+    }
+
+    #[inline]
+    fn validate_distortion(value: &Waveshaper) -> Result<(), ValidationError> {
+        if value.is_valid() {
+            Ok(())
+        } else {
+            Err(ValidationError::new(
+                "Distortion drive must be non-negative, mix between 0 and 1, and a curve at least two finite points",
+            ))
+        }
+    }
+
     #[inline]
     fn sample_rate_default() -> u32 {
         44100
     }
 
+    #[inline]
+    fn oversampling_default() -> usize {
+        1
+    }
+
+    #[inline]
+    fn sustain_level_default() -> f64 {
+        100.
+    }
+
     #[inline]
     fn amplification_default() -> f64 {
         100.
@@ -1,8 +1,10 @@
-use crate::traits::{Duration, Proc};
+use crate::convert::{Converter, Dither, Rounding, SampleFormat};
+use crate::resample::Decimator;
+use crate::traits::{Duration, Proc, ProcState, SampleRate};
 
 pub struct Synth<W, E>
 where
-    W: Proc,
+    W: ProcState,
     E: Proc + Duration,
 {
     sample_rate: u32,
@@ -11,28 +13,59 @@ where
     duration: f64,
     waveform: W,
     envelope: E,
+    inner_t: f64,
+    inner_dt: f64,
+    decimator: Option<Decimator>,
 }
 
 impl<W, E> Synth<W, E>
 where
-    W: Proc,
+    W: ProcState,
     E: Proc + Duration,
 {
     pub fn new(sample_rate: u32, waveform: W, envelope: E) -> Option<Self> {
-        if sample_rate == 0 {
+        Self::new_oversampled(sample_rate, 1, waveform, envelope)
+    }
+
+    pub fn new_oversampled(
+        sample_rate: u32,
+        oversampling: usize,
+        waveform: W,
+        envelope: E,
+    ) -> Option<Self> {
+        if sample_rate == 0 || oversampling == 0 {
             None
         } else {
-            Some(Self {
+            let dt = 1. / sample_rate as f64;
+            let mut synth = Self {
                 sample_rate,
                 t: 0.,
-                dt: 1. / sample_rate as f64,
+                dt,
                 duration: envelope.duration(),
                 waveform,
                 envelope,
-            })
+                inner_t: 0.,
+                inner_dt: dt / oversampling as f64,
+                decimator: None,
+            };
+            if oversampling > 1 {
+                let mut decimator = Decimator::new(oversampling);
+                for _ in 0..decimator.priming() {
+                    decimator.push(synth.generate());
+                }
+                synth.decimator = Some(decimator);
+            }
+            Some(synth)
         }
     }
 
+    fn generate(&mut self) -> f64 {
+        let w = self.waveform.next_value(self.inner_t);
+        let w = w * self.envelope.value(self.inner_t);
+        self.inner_t += self.inner_dt;
+        w
+    }
+
     pub fn render_64<T>(self) -> crate::Samples<T>
     where
         T: From<f64>,
@@ -56,53 +89,89 @@ where
     pub fn render_16<T>(self) -> crate::Samples<T>
     where
         T: From<i16>,
+    {
+        let mut converter = Converter::new(Rounding::TowardZero, Dither::None);
+        crate::Samples::<T> {
+            sample_rate: self.sample_rate,
+            samples: self.map(|s| converter.convert::<i16>(s).into()).collect(),
+        }
+    }
+
+    pub fn render_as<T>(self, converter: &mut Converter) -> crate::Samples<T>
+    where
+        T: SampleFormat,
     {
         crate::Samples::<T> {
             sample_rate: self.sample_rate,
-            samples: self
-                .map(|s| ((s * i16::MAX as f64) as i16).into())
-                .collect(),
+            samples: self.map(|s| converter.convert(s)).collect(),
         }
     }
 }
 
 impl<W, E> crate::traits::Synth for Synth<W, E>
 where
-    W: Proc,
+    W: ProcState,
     E: Proc + Duration,
 {
 }
 
 unsafe impl<W, E> Send for Synth<W, E>
 where
-    W: Proc,
+    W: ProcState,
     E: Proc + Duration,
 {
 }
 
 impl<W, E> Duration for Synth<W, E>
 where
-    W: Proc,
+    W: ProcState,
     E: Proc + Duration,
 {
     fn duration(&self) -> f64 {
-        self.duration
+        if self.duration.is_infinite() {
+            self.envelope.duration()
+        } else {
+            self.duration
+        }
+    }
+}
+
+impl<W, E> SampleRate for Synth<W, E>
+where
+    W: ProcState,
+    E: Proc + Duration,
+{
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
     }
 }
 
 impl<W, E> Iterator for Synth<W, E>
 where
-    W: Proc,
+    W: ProcState,
     E: Proc + Duration,
 {
     type Item = f64;
 
     fn next(&mut self) -> Option<Self::Item> {
+        if self.duration.is_infinite() {
+            // open-ended until a sustained envelope is released, which happens on the output clock
+            self.envelope.latch_release(self.t);
+            self.duration = self.envelope.duration();
+        }
         if self.t >= self.duration {
             return None;
         }
-        let w = self.waveform.value(self.t);
-        let w = w * self.envelope.value(self.t);
+        let w = if let Some(mut decimator) = self.decimator.take() {
+            for _ in 0..decimator.factor() {
+                decimator.push(self.generate());
+            }
+            let w = decimator.output();
+            self.decimator = Some(decimator);
+            w
+        } else {
+            self.generate()
+        };
         self.t += self.dt;
         Some(w)
     }
@@ -0,0 +1,66 @@
+use crate::{
+    bit_crush::{BitCrush, BitCrushable},
+    convert::{Converter, Dither, Rounding},
+    effect::EffectChain,
+    envelope::Envelope,
+    synth::Synth,
+    waveform::Sawtooth,
+};
+
+fn ramp() -> Synth<Sawtooth, Envelope> {
+    let waveform = Sawtooth::new_simple(10.).unwrap();
+    let envelope = Envelope::from_duration(1., 0., 0.1, 0., 0., None).unwrap();
+    Synth::new(1000, waveform, envelope).unwrap()
+}
+
+#[test]
+fn float_crush_matches_integer_mask() {
+    for bits in [BitCrush::B2, BitCrush::B5, BitCrush::B8] {
+        let float: Vec<f64> = ramp().bit_crush(bits).collect();
+        let masked = ramp().bit_crush(bits).render_16::<i16>();
+        assert_eq!(float.len(), masked.samples.len());
+        for (f, m) in float.iter().zip(masked.samples) {
+            assert!((f - m as f64 / i16::MAX as f64).abs() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn crushed_sound_is_a_synth() {
+    let crushed: Vec<f64> = ramp()
+        .bit_crush(BitCrush::B3)
+        .with_downsample(4)
+        .low_pass(400.)
+        .collect();
+    assert_eq!(crushed.len(), 100);
+
+    let held: Vec<f64> = ramp().bit_crush(BitCrush::B16).with_downsample(4).collect();
+    assert!(held.chunks(4).all(|c| c.iter().all(|s| *s == c[0])));
+}
+
+#[test]
+fn converter_applies_to_float_output() {
+    let truncated: Vec<f64> = ramp().bit_crush(BitCrush::B16).collect();
+    let rounded: Vec<f64> = ramp()
+        .bit_crush(BitCrush::B16)
+        .with_converter(Converter::new(Rounding::Nearest, Dither::None))
+        .collect();
+    let masked = ramp()
+        .bit_crush(BitCrush::B16)
+        .with_converter(Converter::new(Rounding::Nearest, Dither::None))
+        .render_16::<i16>();
+    assert_ne!(truncated, rounded);
+    for (f, m) in rounded.iter().zip(masked.samples) {
+        assert!((f - m as f64 / i16::MAX as f64).abs() < 1e-9);
+    }
+
+    let dithered: Vec<f64> = ramp()
+        .bit_crush(BitCrush::B16)
+        .with_converter(Converter::new(Rounding::Nearest, Dither::Tpdf))
+        .collect();
+    assert!(dithered.iter().zip(&rounded).any(|(d, r)| d != r));
+    assert!(dithered
+        .iter()
+        .zip(&rounded)
+        .all(|(d, r)| (d - r).abs() <= 2. / i16::MAX as f64));
+}
@@ -0,0 +1,76 @@
+use super::count_cycles;
+use crate::{
+    control::{Control, Param},
+    effect::{filter::Biquad, Effect},
+    envelope::Envelope,
+    synth::Synth,
+    waveform::Sine,
+};
+
+#[test]
+fn param_glides_to_target() {
+    let param = Param::new(0., 0.01);
+    assert_eq!(param.value(0.), 0.);
+    param.set(1.);
+    assert_eq!(param.target(), 1.);
+    let value = param.value(0.01);
+    assert!((value - (1. - (-1f64).exp())).abs() < 1e-9, "{}", value);
+    assert!((param.value(0.1) - 1.).abs() < 1e-3);
+
+    let param = Param::new(0., 0.);
+    param.set(2.);
+    assert_eq!(param.value(0.001), 2.);
+    param.set(f64::NAN);
+    assert_eq!(param.target(), 2.);
+}
+
+#[test]
+fn set_frequency_from_another_thread() {
+    let sample_rate = 44100;
+    let control = Control::new(100., 1000.);
+    let freq = Envelope::from_duration(100., 0., f64::INFINITY, 0., 0., None)
+        .unwrap()
+        .with_modulation(control.frequency.clone());
+    let envelope = Envelope::from_duration(1., 0., 2., 0., 0., None)
+        .unwrap()
+        .with_modulation(control.amplitude.clone());
+    let mut synth = Synth::new(sample_rate, Sine::new(freq), envelope).unwrap();
+
+    let first: Vec<f64> = synth.by_ref().take(sample_rate as usize).collect();
+    let handle = control.clone();
+    std::thread::spawn(move || {
+        handle.set_frequency(200.);
+        handle.set_amplitude(0.5);
+    })
+    .join()
+    .unwrap();
+    assert_eq!(control.frequency(), 200.);
+    let second: Vec<f64> = synth.collect();
+
+    let cycles = count_cycles(&first);
+    assert!((99..=101).contains(&cycles), "{} cycles", cycles);
+    let cycles = count_cycles(&second);
+    assert!((198..=201).contains(&cycles), "{} cycles", cycles);
+    let peak = second[second.len() / 2..]
+        .iter()
+        .fold(0f64, |peak, s| peak.max(s.abs()));
+    assert!((peak - 0.5).abs() < 0.01, "peak {}", peak);
+}
+
+#[test]
+fn cutoff_control_attenuates() {
+    let sample_rate = 44100;
+    let cutoff = Param::new(20000., 0.);
+    let mut filter = Biquad::low_pass(sample_rate, 20000.).with_cutoff_control(cutoff.clone());
+    let tone = |i: usize| (std::f64::consts::TAU * 5000. * i as f64 / sample_rate as f64).sin();
+    let peak = |filter: &mut Biquad, range: std::ops::Range<usize>| {
+        range
+            .map(|i| filter.process(tone(i)))
+            .skip(1000)
+            .fold(0f64, |peak, s| peak.max(s.abs()))
+    };
+
+    assert!(peak(&mut filter, 0..4410) > 0.9);
+    cutoff.set(200.);
+    assert!(peak(&mut filter, 4410..8820) < 0.01);
+}
@@ -0,0 +1,34 @@
+use crate::convert::{Converter, Dither, Rounding, I24};
+
+#[test]
+fn conversion_clamps_out_of_range_samples() {
+    let mut converter = Converter::default();
+    assert_eq!(converter.convert::<i16>(1.5), i16::MAX);
+    assert_eq!(converter.convert::<i16>(-1.5), i16::MIN);
+    assert_eq!(converter.convert::<i8>(0.5), 64);
+    assert_eq!(converter.convert::<u8>(-1.), 1);
+    assert_eq!(converter.convert::<u8>(0.), 128);
+    assert_eq!(converter.convert::<I24>(1.).value(), I24::MAX);
+    assert_eq!(converter.convert::<i32>(-2.), i32::MIN);
+    assert_eq!(converter.convert::<f32>(2.), 1.);
+
+    let mut truncating = Converter::new(Rounding::TowardZero, Dither::None);
+    assert_eq!(truncating.convert::<i8>(-0.999), -126);
+}
+
+#[test]
+fn tpdf_dither_preserves_sub_lsb_level() {
+    // A constant a quarter of a step above zero rounds to zero without dither,
+    // but dithering keeps its average.
+    let level = 0.25 / i8::MAX as f64;
+    for dither in [Dither::Tpdf, Dither::TpdfShaped] {
+        let mut converter = Converter::new(Rounding::Nearest, dither);
+        let n = 100_000;
+        let mean = (0..n)
+            .map(|_| converter.convert::<i8>(level) as f64)
+            .sum::<f64>()
+            / n as f64;
+        assert!((mean - 0.25).abs() < 0.02, "{:?}: {}", dither, mean);
+    }
+    assert_eq!(Converter::default().convert::<i8>(level), 0);
+}
@@ -0,0 +1,178 @@
+use crate::{
+    effect::{
+        delay::Delay,
+        filter::{Biquad, FilterType},
+        reverb::Convolution,
+        waveshaper::Shape,
+        Effect, EffectChain,
+    },
+    envelope::Envelope,
+    synth::Synth,
+    traits::{Duration, SampleRate},
+    waveform::{Sine, Square},
+    Samples,
+};
+
+fn sine(freq: f64) -> Box<dyn crate::traits::Synth> {
+    let waveform = Sine::new_simple(freq).unwrap();
+    let envelope = Envelope::from_duration(1., 0., 0.1, 0., 0., None).unwrap();
+    Box::new(Synth::new(44100, waveform, envelope).unwrap())
+}
+
+fn peak(sound: impl Iterator<Item = f64>) -> f64 {
+    sound.skip(1000).fold(0., |m, s| s.abs().max(m))
+}
+
+#[test]
+fn filters_chain_on_boxed_synths() {
+    assert!(peak(sine(200.).low_pass(2000.)) > 0.95);
+    assert!(peak(sine(10000.).low_pass(2000.)) < 0.1);
+    assert!(peak(sine(200.).high_pass(2000.)) < 0.05);
+    assert!(peak(sine(200.).high_pass(20.).low_pass(5000.)) > 0.95);
+}
+
+#[test]
+fn effect_tail_extends_duration() {
+    let sound = sine(200.).flanger(0.005, 0.002, 0.5, 0.5, 0.5);
+    assert!(sound.duration() > 0.1);
+    let expected = (sound.duration() * sound.sample_rate() as f64).ceil() as usize;
+    assert!(sound.count().abs_diff(expected) <= 1);
+}
+
+#[test]
+fn downsample_holds_at_swept_rate() {
+    let count_changes = |samples: &[f64]| samples.windows(2).filter(|w| w[0] != w[1]).count();
+
+    let rate = Envelope::from_points(vec![(0., 1000.), (0.1, 1000.)], None).unwrap();
+    let held: Vec<f64> = sine(200.).downsample(rate).collect();
+    assert!(count_changes(&held).abs_diff(100) <= 1);
+
+    let sweep = Envelope::from_points(vec![(0., 1000.), (0.1, 3000.)], None).unwrap();
+    let held: Vec<f64> = sine(200.).downsample(sweep).collect();
+    assert!(count_changes(&held).abs_diff(200) <= 2);
+    assert!(count_changes(&held[..2205]) < count_changes(&held[2205..]));
+}
+
+#[test]
+fn phaser_notches_at_center() {
+    let center = |fc: f64| Envelope::from_points(vec![(0., fc), (1., fc)], None).unwrap();
+    let notch = 2000. * (std::f64::consts::PI / 8.).tan();
+    assert!(peak(sine(notch).phaser(4, center(2000.), 0., 0.5)) < 0.05);
+    assert!(peak(sine(100.).phaser(4, center(2000.), 0., 0.5)) > 0.9);
+
+    let swept: Vec<f64> = sine(1000.).sfxr_phaser(0.2, 0.05).collect();
+    assert_eq!(swept.len(), sine(1000.).count());
+    assert!(swept.iter().all(|s| s.is_finite() && s.abs() <= 1.));
+}
+
+#[test]
+fn convolution_matches_direct_convolution() {
+    let impulse: Vec<f64> = (0..1000)
+        .map(|i| (i as f64 * 0.37).sin() * (-(i as f64) / 300.).exp())
+        .collect();
+    let input: Vec<f64> = (0..700).map(|i| (i as f64 * 0.11).cos()).collect();
+    let expected: Vec<f64> = (0..input.len() + impulse.len() - 1)
+        .map(|n| {
+            (0..=n)
+                .filter_map(|j| Some(impulse.get(j)? * input.get(n - j)?))
+                .sum()
+        })
+        .collect();
+
+    let samples = Samples {
+        sample_rate: 44100,
+        samples: impulse,
+    };
+    let mut convolution = Convolution::new(44100, samples, 1.);
+    let output: Vec<f64> = (0..expected.len())
+        .map(|n| convolution.process(input.get(n).copied().unwrap_or(0.)))
+        .collect();
+    for (y, e) in output.iter().zip(&expected) {
+        assert!((y - e).abs() < 1e-9, "{} {}", y, e);
+    }
+}
+
+#[test]
+fn reverbs_extend_duration() {
+    let impulse = Samples {
+        sample_rate: 22050,
+        samples: vec![0.5; 2205],
+    };
+    let sound = sine(200.).convolve(impulse, 0.5);
+    assert!((sound.duration() - 0.2).abs() < 1e-3);
+    let expected = (sound.duration() * sound.sample_rate() as f64).ceil() as usize;
+    assert!(sound.count().abs_diff(expected) <= 1);
+
+    let sound = sine(200.).reverb(0.8, 0.5, 0.3);
+    assert!(sound.duration() > 1.);
+    let samples: Vec<f64> = sound.collect();
+    assert!(samples.iter().all(|s| s.is_finite()));
+    let loudest = |window: &[f64]| window.iter().fold(0., |m: f64, s| s.abs().max(m));
+    let end = samples.len() - 4410;
+    assert!(loudest(&samples[6000..end]) > 10. * loudest(&samples[end..]));
+}
+
+#[test]
+fn delay_repeats_until_tail_ends() {
+    let click = || {
+        let envelope = Envelope::from_points(vec![(0., 1.), (0.001, 1.)], None).unwrap();
+        Synth::new(1000, Square::default_simple(1.).unwrap(), envelope).unwrap()
+    };
+    let echoes: Vec<f64> = click().delay(0.1, 0.5, 1.).collect();
+    // Ten repeats halving each time fade by 60 dB, the first one after 0.1 s.
+    assert_eq!(echoes.len(), 1 + 11 * 100);
+    for (k, gain) in [(1, 1.), (2, 0.5), (3, 0.25), (10, 0.5f64.powi(9))] {
+        assert!((echoes[k * 100] - gain).abs() < 1e-9, "{}", k);
+    }
+
+    let filtered =
+        click().effect(Delay::new(1000, 0.1, 0.9, 1.).with_filter(Biquad::low_pass(1000, 50.)));
+    let filtered: Vec<f64> = filtered.collect();
+    // The first repeat is unfiltered, the second one went through the low-pass.
+    assert!((filtered[100] - 1.).abs() < 1e-9);
+    assert!(filtered[150..300].iter().all(|s| s.abs() < 0.5));
+
+    // A resonant filter boosts each repeat by its peak gain of about 5, so the loop gain
+    // is about 0.5 rather than 0.1 and twelve repeats rather than four are needed.
+    let resonant = Biquad::new(FilterType::LowPass, 1000, 50., 5.);
+    assert!((resonant.peak_gain() - 5.0252).abs() < 1e-4);
+    let delay = Delay::new(1000, 0.1, 0.1, 1.).with_filter(resonant);
+    assert!((delay.tail() - 1.2).abs() < 1e-9);
+}
+
+#[test]
+fn waveshaper_shapes() {
+    let curve = Shape::Curve {
+        points: vec![-1., 0., 1.],
+    };
+    for (shape, input, output) in [
+        (Shape::Tanh, 0.5, 0.5f64.tanh()),
+        (Shape::HardClip, 1.5, 1.),
+        (Shape::HardClip, -3., -1.),
+        (Shape::Foldback, 1.25, 0.75),
+        (Shape::Foldback, -1.5, -0.5),
+        (Shape::Foldback, 3.5, -0.5),
+        (curve.clone(), 0.25, 0.25),
+        (curve, 2., 1.),
+    ] {
+        assert!((shape.apply(input) - output).abs() < 1e-9, "{:?}", shape);
+    }
+
+    assert!(peak(sine(200.).distort(Shape::HardClip, 10., 1.).unwrap()) <= 1.);
+    let half_wet = sine(200.).distort(Shape::HardClip, 0.5, 0.5).unwrap();
+    assert!((peak(half_wet) - 0.75).abs() < 1e-3);
+    assert!(sine(200.)
+        .distort(Shape::Curve { points: vec![] }, 1., 1.)
+        .is_none());
+}
+
+#[test]
+fn flanger_tail_covers_feedback() {
+    let sound = sine(200.).flanger(0.005, 0.002, 0.5, 0.9, 1.);
+    assert!(sound.duration() > 0.4, "{}", sound.duration());
+    let samples: Vec<f64> = sound.collect();
+    let end = samples[samples.len() - 441..]
+        .iter()
+        .fold(0., |m: f64, s| s.abs().max(m));
+    assert!(end < 0.01, "{}", end);
+}
@@ -0,0 +1,213 @@
+use crate::{
+    envelope::{Curve, Envelope, EnvelopeError, Release},
+    synth::Synth,
+    traits::{Duration, Proc},
+    waveform::Sine,
+};
+
+fn midpoint(start: f64, end: f64, curve: Curve) -> f64 {
+    let envelope = Envelope::from_curves(vec![(0., start, curve), (1., end, curve)], None).unwrap();
+    envelope.value(0.5)
+}
+
+#[test]
+fn segment_curves() {
+    assert!((midpoint(1., 0., Curve::Linear) - 0.5).abs() < 1e-9);
+    assert!((midpoint(880., 440., Curve::Exponential) - 440. * 2f64.sqrt()).abs() < 1e-6);
+    assert!(midpoint(1., 0., Curve::Exponential) < 0.05);
+    assert!(midpoint(1., 0., Curve::Logarithmic) > 0.95);
+    assert!((midpoint(0., 1., Curve::Power(2.)) - 0.25).abs() < 1e-9);
+    assert!((midpoint(0., 1., Curve::Bezier(0., 1.)) - 0.5).abs() < 1e-9);
+    assert!(Envelope::from_curves(
+        vec![(0., 0., Curve::Linear), (1., 1., Curve::Power(0.))],
+        None
+    )
+    .is_none());
+}
+
+#[test]
+fn sustain_holds_until_release() {
+    let release = Release::new();
+    let envelope = Envelope::from_points(vec![(0., 0.), (0.1, 1.), (0.2, 1.), (0.3, 0.)], None)
+        .unwrap()
+        .with_sustain(0.15, 0.15, &release)
+        .unwrap();
+    let mut synth = Synth::new(1000, Sine::new_simple(100.).unwrap(), envelope).unwrap();
+    assert!(synth.duration().is_infinite());
+    assert_eq!(synth.by_ref().take(1000).count(), 1000);
+
+    release.release();
+    let tail = synth.count();
+    assert!(tail.abs_diff(150) <= 1, "{}", tail);
+}
+
+#[test]
+fn release_follows_output_clock() {
+    for oversampling in [1, 8] {
+        let release = Release::new();
+        let envelope = Envelope::from_points(vec![(0., 0.), (0.1, 1.), (0.3, 0.)], None)
+            .unwrap()
+            .with_sustain(0.1, 0.1, &release)
+            .unwrap();
+        let sine = Sine::new_simple(100.).unwrap();
+        let mut synth = Synth::new_oversampled(1000, oversampling, sine, envelope).unwrap();
+        assert_eq!(synth.by_ref().take(500).count(), 500);
+
+        release.release();
+        assert_eq!(synth.count(), 200, "{}", oversampling);
+    }
+}
+
+#[test]
+fn loop_repeats_until_release() {
+    let release = Release::new();
+    let envelope = Envelope::from_points(vec![(0., 0.), (0.1, 1.), (0.2, 0.)], None)
+        .unwrap()
+        .with_sustain(0., 0.2, &release)
+        .unwrap();
+    assert!((envelope.value(1.05) - envelope.value(0.05)).abs() < 1e-9);
+    assert!(envelope.duration().is_infinite());
+
+    release.release();
+    envelope.value(5.);
+    assert!(envelope.duration().is_infinite());
+    envelope.latch_release(1.05);
+    assert!((envelope.value(1.05) - 0.5).abs() < 1e-9);
+    assert!((envelope.duration() - 1.2).abs() < 1e-9);
+    assert_eq!(envelope.value(1.3), 0.);
+}
+
+#[test]
+fn adsr_decays_to_sustain_level() {
+    let envelope = Envelope::from_adsr(1., 0.1, 0.1, 0.5, 0.2, 0.1, None).unwrap();
+    assert!((envelope.value(0.05) - 0.5).abs() < 1e-9);
+    assert!((envelope.value(0.1) - 1.).abs() < 1e-9);
+    assert!((envelope.value(0.15) - 0.75).abs() < 1e-9);
+    assert!((envelope.value(0.3) - 0.5).abs() < 1e-9);
+    assert!((envelope.value(0.45) - 0.25).abs() < 1e-9);
+    assert!((envelope.duration() - 0.5).abs() < 1e-9);
+    assert!(Envelope::from_adsr(1., 0., 0., 0.5, 0., 0., None).is_none());
+
+    let freq = Envelope::from_adsr(440., 0., 0.1, 2., f64::INFINITY, 0., None).unwrap();
+    assert!((freq.value(0.05) - 660.).abs() < 1e-9);
+    assert!((freq.value(10.) - 880.).abs() < 1e-9);
+}
+
+#[test]
+fn lookup_order_does_not_matter() {
+    let points = (0..=100).map(|i| (i as f64, (i % 7) as f64)).collect();
+    let envelope = Envelope::from_points(points, None).unwrap();
+    let expected = |t: f64| {
+        let i = t.floor();
+        let (a, b) = ((i % 7.), ((i + 1.) % 7.));
+        a + (b - a) * (t - i)
+    };
+    for t in [50.5, 0.25, 99.75, 3.5, 3.75, 4.25, 42., 0.] {
+        assert!((envelope.value(t) - expected(t)).abs() < 1e-9, "{}", t);
+    }
+    assert_eq!(envelope.value(100.5), 0.);
+    assert!((envelope.duration() - 100.).abs() < 1e-9);
+}
+
+#[test]
+fn try_from_points_reports_errors() {
+    let envelope =
+        Envelope::try_from_points(vec![(0., 0.), (0.5, 1.), (0.5, 0.5), (1., 0.)], None).unwrap();
+    assert!((envelope.value(0.25) - 0.5).abs() < 1e-9);
+    assert!((envelope.value(0.75) - 0.25).abs() < 1e-9);
+    assert!((envelope.duration() - 1.).abs() < 1e-9);
+
+    assert_eq!(
+        Envelope::try_from_points(vec![(0., 0.), (1., 1.), (0.5, 0.)], None).err(),
+        Some(EnvelopeError::NonMonotonicTime {
+            index: 2,
+            time: 0.5,
+            previous: 1.
+        })
+    );
+    assert_eq!(
+        Envelope::try_from_points(vec![(0., 0.), (f64::NAN, 1.)], None).err(),
+        Some(EnvelopeError::NaN { index: 1 })
+    );
+    assert_eq!(
+        Envelope::try_from_points(vec![(-1., 0.), (1., 1.)], None).err(),
+        Some(EnvelopeError::NegativeTime {
+            index: 0,
+            time: -1.
+        })
+    );
+    assert_eq!(
+        Envelope::try_from_points(vec![(0., 1.), (f64::INFINITY, 1.)], None).err(),
+        Some(EnvelopeError::InfiniteDuration)
+    );
+    assert_eq!(
+        Envelope::try_from_points(vec![(0., 1.), (1., 0.)], Some((2., 5.))).err(),
+        Some(EnvelopeError::InvalidVibrato {
+            depth: 2.,
+            freq: 5.
+        })
+    );
+    assert_eq!(
+        Envelope::try_from_points(vec![], None).err(),
+        Some(EnvelopeError::ZeroDuration)
+    );
+    assert_eq!(
+        Envelope::try_from_points(vec![(0., 0.), (0., 1.)], None).err(),
+        Some(EnvelopeError::ZeroDuration)
+    );
+}
+
+#[test]
+fn try_from_duration_and_adsr_report_errors() {
+    assert!(Envelope::try_from_duration(1., 0.1, 0.5, 0.2, 0., None).is_ok());
+    assert!(Envelope::try_from_adsr(1., 0.1, 0.1, 0.5, 0.5, 0.2, None).is_ok());
+
+    assert_eq!(
+        Envelope::try_from_duration(1., -0.1, 0.5, 0.2, 0., None).err(),
+        Some(EnvelopeError::NegativeDuration {
+            segment: "attack",
+            duration: -0.1
+        })
+    );
+    assert_eq!(
+        Envelope::try_from_duration(1., 0., f64::INFINITY, 0., 0., None).err(),
+        Some(EnvelopeError::InfiniteDuration)
+    );
+    // The Option builders still allow envelopes that hold forever.
+    assert!(Envelope::from_duration(1., 0., f64::INFINITY, 0., 0., None).is_some());
+    assert_eq!(
+        Envelope::try_from_duration(0., 0.1, 0.5, 0.2, 0., None).err(),
+        Some(EnvelopeError::NonPositiveAmplitude { amp: 0. })
+    );
+    assert_eq!(
+        Envelope::try_from_duration(1., 0., 0., 0., 0., None).err(),
+        Some(EnvelopeError::ZeroDuration)
+    );
+    assert!(matches!(
+        Envelope::try_from_duration(1., 0.1, 0.5, 0.2, f64::NAN, None),
+        Err(EnvelopeError::NonFinite {
+            parameter: "sustain_punch",
+            ..
+        })
+    ));
+
+    assert_eq!(
+        Envelope::try_from_adsr(1., 0.1, 0.1, -0.5, 0.5, 0.2, None).err(),
+        Some(EnvelopeError::NegativeSustainLevel { level: -0.5 })
+    );
+    assert_eq!(
+        Envelope::try_from_adsr(1., 0.1, 0.1, 0.5, 0.5, -0.2, None).err(),
+        Some(EnvelopeError::NegativeDuration {
+            segment: "release",
+            duration: -0.2
+        })
+    );
+    assert_eq!(
+        Envelope::try_from_adsr(1., 0.1, 0.1, 0.5, 0.5, 0.2, Some((0.5, -1.))).err(),
+        Some(EnvelopeError::InvalidVibrato {
+            depth: 0.5,
+            freq: -1.
+        })
+    );
+    assert!(Envelope::from_adsr(1., 0.1, 0.1, -0.5, 0.5, 0.2, None).is_none());
+}
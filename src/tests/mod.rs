@@ -1,21 +1,22 @@
-use crate::serde::{Description, WaveformType};
+mod bit_crush;
+mod control;
+mod convert;
+mod effect;
+mod envelope;
+mod noise;
+#[cfg(feature = "playback")]
+mod playback;
+mod resample;
+mod sampler;
+#[cfg(feature = "json")]
+mod serde;
+mod synth;
+mod waveform;
 
-#[test]
-fn serde_json_serialize() {
-    let description = Description {
-        fxr_version: 1,
-        fxr_name: "test".to_string(),
-        sample_rate: 44100,
-        attack: 0.,
-        sustain: 1.,
-        decay: 0.,
-        sustain_punch: 0.,
-        amplification: 100.,
-        frequency: 200.,
-        // waveform: WaveformType::Square { square_duty: 0.5 },
-        waveform: WaveformType::BrownNoise,
-    };
-    let jfxr = serde_json::to_value::<Description>(description).unwrap();
-    assert!(jfxr.is_object());
-    println!("{}", jfxr);
+/// Upward zero crossings, one per cycle of a periodic signal.
+fn count_cycles(samples: &[f64]) -> usize {
+    samples
+        .windows(2)
+        .filter(|w| w[0] < 0. && w[1] >= 0.)
+        .count()
 }
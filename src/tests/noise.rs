@@ -0,0 +1,150 @@
+use realfft::RealFftPlanner;
+
+use crate::{
+    envelope::Envelope,
+    noise::{BlueNoise, BrownNoise, LfsrMode, LfsrNoise, PinkNoise, VioletNoise, WhiteNoise},
+    traits::ProcState,
+    waveform::Arpeggio,
+};
+
+const SAMPLE_RATE: f64 = 44100.;
+const FRAME: usize = 1024;
+
+fn render(mut noise: impl ProcState, seconds: f64) -> Vec<f64> {
+    (1..=(seconds * SAMPLE_RATE) as usize)
+        .map(|i| noise.next_value(i as f64 / SAMPLE_RATE))
+        .collect()
+}
+
+/// Power spectrum averaged over Hann-windowed frames.
+fn power_spectrum(samples: &[f64]) -> Vec<f64> {
+    let fft = RealFftPlanner::<f64>::new().plan_fft_forward(FRAME);
+    let mut power = vec![0.; FRAME / 2 + 1];
+    for frame in samples.chunks_exact(FRAME) {
+        let mut input: Vec<f64> = frame
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let window = 0.5 - 0.5 * (std::f64::consts::TAU * i as f64 / FRAME as f64).cos();
+                s * window
+            })
+            .collect();
+        let mut spectrum = fft.make_output_vec();
+        fft.process(&mut input, &mut spectrum).unwrap();
+        power
+            .iter_mut()
+            .zip(spectrum)
+            .for_each(|(p, c)| *p += c.norm_sqr());
+    }
+    power
+}
+
+/// Mean power between `low` and `high` Hz, in dB.
+fn band_db(spectrum: &[f64], low: f64, high: f64) -> f64 {
+    let bin = |f: f64| (f / SAMPLE_RATE * FRAME as f64).round() as usize;
+    let band = &spectrum[bin(low)..bin(high)];
+    10. * (band.iter().sum::<f64>() / band.len() as f64).log10()
+}
+
+/// Spectral slope between 500 Hz and 8 kHz, in dB per octave.
+fn slope(samples: &[f64]) -> f64 {
+    let spectrum = power_spectrum(samples);
+    (band_db(&spectrum, 4000., 8000.) - band_db(&spectrum, 500., 1000.)) / 3.
+}
+
+#[test]
+fn noise_colors_roll_off() {
+    let white = slope(&render(WhiteNoise::new_simple(SAMPLE_RATE).unwrap(), 4.));
+    assert!(white.abs() < 0.5, "white {}", white);
+
+    let colors = [
+        (
+            "pink",
+            slope(&render(PinkNoise::new_simple(SAMPLE_RATE).unwrap(), 4.)),
+            -3.,
+        ),
+        (
+            "brown",
+            slope(&render(
+                BrownNoise::default_simple(SAMPLE_RATE).unwrap(),
+                4.,
+            )),
+            -6.,
+        ),
+        (
+            "blue",
+            slope(&render(BlueNoise::new_simple(SAMPLE_RATE).unwrap(), 4.)),
+            3.,
+        ),
+        (
+            "violet",
+            slope(&render(VioletNoise::new_simple(SAMPLE_RATE).unwrap(), 4.)),
+            6.,
+        ),
+    ];
+    for (name, slope, expected) in colors {
+        assert!((slope - expected).abs() < 1., "{} {}", name, slope);
+    }
+}
+
+#[test]
+fn blue_and_violet_noise_stay_in_range() {
+    for freq in [SAMPLE_RATE, 10. * SAMPLE_RATE] {
+        let blue = render(BlueNoise::new_simple(freq).unwrap(), 2.);
+        assert!(blue.iter().all(|s| s.abs() <= 1.));
+        let violet = render(VioletNoise::new_simple(freq).unwrap(), 2.);
+        assert!(violet.iter().all(|s| s.abs() <= 1.));
+    }
+}
+
+#[test]
+fn noise_draws_at_declared_frequency() {
+    // Interpolating between values drawn at 2 kHz leaves little above it.
+    let spectrum = power_spectrum(&render(WhiteNoise::new_simple(2000.).unwrap(), 4.));
+    let rolloff = band_db(&spectrum, 250., 500.) - band_db(&spectrum, 4000., 8000.);
+    assert!(rolloff > 20., "{}", rolloff);
+
+    // The 93-step sequence clocked at 930 Hz repeats every tenth of a second.
+    let bits = render(LfsrNoise::new_simple(930., LfsrMode::Short).unwrap(), 1.);
+    let period = SAMPLE_RATE as usize / 10;
+    let mismatches = bits.iter().zip(&bits[period..]).filter(|(a, b)| a != b);
+    assert!(mismatches.count() < bits.len() / 100);
+
+    let bits = render(LfsrNoise::new_simple(32767., LfsrMode::Long).unwrap(), 2.5);
+    let period = SAMPLE_RATE as usize;
+    let mismatches = bits.iter().zip(&bits[period..]).filter(|(a, b)| a != b);
+    assert!(mismatches.count() < bits.len() / 100);
+    let shifted = bits.iter().zip(&bits[period / 2..]).filter(|(a, b)| a != b);
+    assert!(shifted.count() > bits.len() / 4);
+}
+
+#[test]
+fn noise_above_sample_rate() {
+    let white = render(WhiteNoise::new_simple(10. * SAMPLE_RATE).unwrap(), 2.);
+    assert!(white.iter().all(|s| s.abs() <= 1.));
+    assert!(slope(&white).abs() < 0.5);
+
+    let brown = render(BrownNoise::default_simple(10. * SAMPLE_RATE).unwrap(), 2.);
+    assert!(brown.iter().all(|s| s.abs() <= 1.));
+    assert!((slope(&brown) + 6.).abs() < 1.);
+}
+
+#[test]
+fn brown_noise_rolloff_bounds() {
+    let freq = || Envelope::from_duration(SAMPLE_RATE, 0., f64::INFINITY, 0., 0., None).unwrap();
+    assert!(BrownNoise::new(freq(), 0.).is_none());
+    assert!(BrownNoise::new(freq(), 1.).is_none());
+    assert!(BrownNoise::new(freq(), f64::NAN).is_none());
+    let samples = render(BrownNoise::new(freq(), 0.5).unwrap(), 0.1);
+    assert!(samples.iter().all(|s| (-1. ..=1.).contains(s)));
+}
+
+#[test]
+fn noise_follows_arpeggio() {
+    let octave = Arpeggio::new(1., vec![12.]).unwrap();
+    let arpeggiated = LfsrNoise::new_simple(1000., LfsrMode::Short)
+        .unwrap()
+        .with_arpeggio(octave);
+    let doubled = LfsrNoise::new_simple(2000., LfsrMode::Short).unwrap();
+    assert_eq!(render(arpeggiated, 0.1), render(doubled, 0.1));
+}
@@ -0,0 +1,67 @@
+use crate::{
+    envelope::Envelope,
+    playback::{MemorySink, Player},
+    synth::Synth,
+    waveform::Square,
+};
+
+fn blip(duration: f64) -> Synth<Square, Envelope> {
+    let waveform = Square::default_simple(100.).unwrap();
+    let envelope = Envelope::from_duration(0.5, 0., duration, 0., 0., None).unwrap();
+    Synth::new(1000, waveform, envelope).unwrap()
+}
+
+#[test]
+fn overlapping_sounds_are_mixed_and_reported_finished() {
+    let player = Player::new(MemorySink::new(1000, 2)).unwrap();
+    let short = player.play(blip(0.01)).unwrap();
+    let long = player.play(blip(0.02)).unwrap();
+
+    let data = player.backend().pull(5);
+    assert_eq!(data.len(), 10);
+    assert!(data.iter().all(|s| *s == 1.));
+
+    player.backend().pull(10);
+    assert!(short.is_finished());
+    assert!(!long.is_finished());
+
+    let data = player.backend().pull(20);
+    assert!(data[..8].iter().all(|s| s.abs() == 0.5));
+    assert!(data[20..].iter().all(|s| *s == 0.));
+    assert!(long.wait_timeout(std::time::Duration::ZERO));
+}
+
+#[test]
+fn invalid_backend_rate_is_reported() {
+    let player = Player::new(MemorySink::new(0, 1)).unwrap();
+    assert!(player.play(blip(0.01)).is_err());
+}
+
+#[test]
+fn stopped_sounds_leave_the_mix() {
+    let player = Player::new(MemorySink::new(1000, 1)).unwrap();
+    let short = player.play(blip(0.01)).unwrap();
+    let long = player.play(blip(1.)).unwrap();
+
+    long.stop();
+    assert!(long.is_finished());
+    let data = player.backend().pull(5);
+    assert!(data.iter().all(|s| s.abs() == 0.5));
+
+    player.stop_all();
+    assert!(short.wait_timeout(std::time::Duration::ZERO));
+    assert!(player.backend().pull(5).iter().all(|s| *s == 0.));
+}
+
+#[test]
+fn dropping_the_player_ends_waits() {
+    let player = Player::new(MemorySink::new(1000, 1)).unwrap();
+    let playback = player.play(blip(1.)).unwrap();
+    let waiter = {
+        let playback = playback.clone();
+        std::thread::spawn(move || playback.wait())
+    };
+    drop(player);
+    waiter.join().unwrap();
+    assert!(playback.is_finished());
+}
@@ -0,0 +1,45 @@
+use crate::{
+    envelope::Envelope,
+    resample::{Resamplable, Resampled},
+    synth::Synth,
+    traits::SampleRate,
+    waveform::Sine,
+};
+
+fn sine(sample_rate: u32) -> Synth<Sine, Envelope> {
+    let waveform = Sine::new_simple(1000.).unwrap();
+    let envelope = Envelope::from_duration(1., 0., 0.5, 0., 0., None).unwrap();
+    Synth::new(sample_rate, waveform, envelope).unwrap()
+}
+
+#[test]
+fn resample_preserves_pitch_and_level() {
+    for sample_rate in [22050, 48000] {
+        let samples = sine(44100).render_64::<f64>().resample(sample_rate);
+        assert_eq!(samples.sample_rate, sample_rate);
+        assert_eq!(samples.samples.len(), sample_rate as usize / 2);
+
+        let inner = &samples.samples[100..samples.samples.len() - 100];
+        let peak = inner.iter().fold(0f64, |m, s| m.max(s.abs()));
+        assert!((peak - 1.).abs() < 0.01, "peak {}", peak);
+        let cycles = inner.windows(2).filter(|w| w[0] < 0. && w[1] >= 0.).count() as f64;
+        let seconds = inner.len() as f64 / sample_rate as f64;
+        assert!(
+            (cycles / seconds - 1000.).abs() < 5.,
+            "{} Hz",
+            cycles / seconds
+        );
+    }
+}
+
+#[test]
+fn streaming_resampler_matches_batch() {
+    let batch = sine(44100).render_64::<f64>().resample(48000);
+    let stream = Resampled::new(sine(44100), 48000).unwrap();
+    assert_eq!(stream.sample_rate(), 48000);
+    let stream: Vec<f64> = stream.collect();
+    assert_eq!(stream.len(), batch.samples.len());
+    for (a, b) in stream.iter().zip(batch.samples.iter()) {
+        assert!((a - b).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,99 @@
+use std::f64::consts::TAU;
+
+use super::count_cycles;
+use crate::{
+    envelope::Envelope,
+    sampler::{Interpolation, Looping, Sampler},
+    synth::Synth,
+    traits::ProcState,
+    Samples,
+};
+
+/// A tenth of a second of 100 Hz sine, recorded at 8 kHz.
+fn recording() -> Samples<f64> {
+    Samples {
+        sample_rate: 8000,
+        samples: (0..800)
+            .map(|i| (TAU * 100. * i as f64 / 8000.).sin())
+            .collect(),
+    }
+}
+
+#[test]
+fn sampler_shifts_pitch() {
+    for interpolation in [
+        Interpolation::Nearest,
+        Interpolation::Linear,
+        Interpolation::Cubic,
+    ] {
+        let freq = Envelope::from_points(vec![(0., 200.), (1., 200.)], None).unwrap();
+        let sampler = Sampler::new(recording(), 100., freq)
+            .unwrap()
+            .with_interpolation(interpolation);
+        let envelope = Envelope::from_duration(1., 0., 0.1, 0., 0., None).unwrap();
+        let samples: Vec<f64> = Synth::new(44100, sampler, envelope).unwrap().collect();
+        // Twice as fast, the recording ends halfway through.
+        let (played, silent) = samples.split_at(samples.len() / 2);
+        assert!(count_cycles(played).abs_diff(10) <= 1);
+        assert!(silent[10..].iter().all(|s| *s == 0.));
+    }
+}
+
+#[test]
+fn sampler_loops() {
+    let dt = 1. / 8000.;
+    let mut forward = Sampler::new_simple(recording())
+        .unwrap()
+        .with_looping(Looping::Forward { start: 0, end: 800 })
+        .unwrap();
+    let mut reference = Sampler::new_simple(recording()).unwrap();
+    for i in 1..800 {
+        let t = i as f64 * dt;
+        let expected = reference.next_value(t);
+        assert!((forward.next_value(t) - expected).abs() < 1e-9);
+    }
+    for i in 800..1600 {
+        let t = i as f64 * dt;
+        let expected = recording().samples[i - 800];
+        assert!((forward.next_value(t) - expected).abs() < 1e-6);
+    }
+
+    let mut ping_pong = Sampler::new_simple(recording())
+        .unwrap()
+        .with_looping(Looping::PingPong {
+            start: 400,
+            end: 800,
+        })
+        .unwrap();
+    let samples = recording().samples;
+    for i in 1..1600 {
+        let value = ping_pong.next_value(i as f64 * dt);
+        if (800..1198).contains(&i) {
+            assert!((value - samples[799 - (i - 799)]).abs() < 1e-6, "{}", i);
+        }
+    }
+
+    assert!(Sampler::new_simple(recording())
+        .unwrap()
+        .with_looping(Looping::Forward {
+            start: 10,
+            end: 900
+        })
+        .is_none());
+}
+
+#[test]
+fn sampler_loops_stay_precise() {
+    let dt = 1. / 8000.;
+    let mut forward = Sampler::new_simple(recording())
+        .unwrap()
+        .with_looping(Looping::Forward { start: 0, end: 800 })
+        .unwrap();
+    let samples = recording().samples;
+    for i in 1..1_000_000 {
+        let value = forward.next_value(i as f64 * dt);
+        if i >= 999_200 {
+            assert!((value - samples[i % 800]).abs() < 1e-9, "{}", value);
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::{
+    envelope::{Curve, Envelope},
+    noise::BrownNoise,
+    serde::{Description, EnvelopeType, WaveformType},
+    traits::Duration,
+};
+
+#[test]
+fn serde_json_serialize() {
+    let description = Description {
+        fxr_version: 1,
+        fxr_name: "test".to_string(),
+        sample_rate: 44100,
+        oversampling: 1,
+        attack: 0.,
+        sustain: 1.,
+        decay: 0.,
+        sustain_punch: 0.,
+        envelope: EnvelopeType::Jfxr,
+        sustain_level: 100.,
+        release: 0.,
+        attack_curve: Curve::Linear,
+        sustain_curve: Curve::Linear,
+        decay_curve: Curve::Exponential,
+        release_curve: Curve::Linear,
+        amplification: 100.,
+        frequency: 200.,
+        arpeggio: None,
+        distortion: None,
+        phaser_offset: 0.,
+        phaser_sweep: 0.,
+        // waveform: WaveformType::Square { square_duty: 0.5 },
+        waveform: WaveformType::BrownNoise,
+    };
+    let jfxr = serde_json::to_value::<Description>(description).unwrap();
+    assert!(jfxr.is_object());
+    println!("{}", jfxr);
+}
+
+#[test]
+fn serde_json_fm_description() {
+    let description: Description = serde_json::from_str(
+        r#"{"_version": 1, "_name": "fm", "sustain": 0.5, "amplification": 1,
+            "frequency": 220, "waveform": "fm", "ratio": 3.5, "index": 4, "indexEnd": 0}"#,
+    )
+    .unwrap();
+    let mut synth = description.build().unwrap();
+    assert!((synth.duration() - 0.5).abs() < 1e-9);
+    assert!(synth.all(|s| s.is_finite()));
+
+    let description: Result<Description, _> = serde_json::from_str(
+        r#"{"_version": 1, "_name": "ring", "sustain": 0.5,
+            "frequency": 220, "waveform": "ringmod", "modulatorFrequency": 0}"#,
+    );
+    assert!(description.unwrap().build().is_err());
+}
+
+#[test]
+fn serde_json_distortion() {
+    let description: Description = serde_json::from_str(
+        r#"{"_version": 1, "_name": "grit", "sustain": 0.1, "amplification": 1,
+            "frequency": 110, "waveform": "sine",
+            "distortion": {"shape": {"type": "curve", "points": [-0.5, 0, 0.5]}, "drive": 4}}"#,
+    )
+    .unwrap();
+    let synth = description.build().unwrap();
+    assert!(synth.fold(0., |m: f64, s| m.max(s.abs())) <= 0.5 + 1e-9);
+
+    let description: Description = serde_json::from_str(
+        r#"{"_version": 1, "_name": "grit", "sustain": 0.1, "frequency": 110,
+            "waveform": "sine", "distortion": {"shape": {"type": "curve", "points": [1]}}}"#,
+    )
+    .unwrap();
+    assert!(description.build().is_err());
+}
+
+#[test]
+fn serde_json_square_duty_is_a_percentage() {
+    let square = |duty: f64| {
+        serde_json::from_str::<Description>(&format!(
+            r#"{{"_version": 1, "_name": "square", "sustain": 0.1, "amplification": 1,
+                "frequency": 100, "waveform": "square", "squareDuty": {}}}"#,
+            duty
+        ))
+        .unwrap()
+    };
+
+    let samples: Vec<f64> = square(25.).build().unwrap().collect();
+    let high = samples.iter().filter(|s| **s > 0.).count() as f64 / samples.len() as f64;
+    assert!((high - 0.25).abs() < 0.02, "{}", high);
+
+    assert!(square(0.).build().is_err());
+    assert!(square(100.).build().is_err());
+}
+
+#[test]
+fn serde_json_frequency_must_be_positive() {
+    let description: Description = serde_json::from_str(
+        r#"{"_version": 1, "_name": "silent", "sustain": 0.1, "frequency": 0, "waveform": "sine"}"#,
+    )
+    .unwrap();
+    let Err(errors) = description.build() else {
+        panic!("zero frequency accepted");
+    };
+    assert!(errors.field_errors().contains_key("frequency"));
+
+    let envelope = Envelope::from_duration(1., 0., 0.1, 0., 0., None).unwrap();
+    let Err(errors) = WaveformType::Sine.build(44100, 1, 0., None, envelope) else {
+        panic!("zero frequency accepted");
+    };
+    assert!(errors.field_errors().contains_key("frequency"));
+}
+
+#[test]
+fn serde_json_amplification_must_be_positive() {
+    let description: Description = serde_json::from_str(
+        r#"{"_version": 1, "_name": "silent", "sustain": 0.1, "amplification": 0,
+            "frequency": 440, "waveform": "sine"}"#,
+    )
+    .unwrap();
+    let Err(errors) = description.build() else {
+        panic!("zero amplification accepted");
+    };
+    assert!(errors.field_errors().contains_key("envelope"));
+}
+
+#[test]
+fn serde_json_build_controlled() {
+    let description: Description = serde_json::from_str(
+        r#"{"_version": 1, "_name": "controlled", "sustain": 1, "amplification": 1,
+            "frequency": 440, "waveform": "sine"}"#,
+    )
+    .unwrap();
+    let (mut synth, control) = description.build_controlled().unwrap();
+    assert_eq!(control.frequency(), 440.);
+    assert_eq!(control.cutoff(), 22050.);
+
+    let loud = synth
+        .by_ref()
+        .take(22050)
+        .fold(0f64, |peak, s| peak.max(s.abs()));
+    control.set_amplitude(0.);
+    let quiet = synth.skip(4410).fold(0f64, |peak, s| peak.max(s.abs()));
+    assert!(loud > 0.5, "{}", loud);
+    assert!(quiet < 1e-3, "{}", quiet);
+}
+
+#[test]
+fn serde_json_build_controlled_matches_build() {
+    let json = r#"{"_version": 1, "_name": "controlled", "sustain": 1, "amplification": 1,
+        "frequency": 440, "waveform": "sawtooth"}"#;
+    let description = || serde_json::from_str::<Description>(json).unwrap();
+    let expected: Vec<f64> = description().build().unwrap().collect();
+    let (controlled, _) = description().build_controlled().unwrap();
+    assert_eq!(controlled.collect::<Vec<_>>(), expected);
+
+    // the filter is bypassed again once the cutoff glides back up
+    let (mut controlled, control) = description().build_controlled().unwrap();
+    control.set_cutoff(1000.);
+    let filtered: Vec<f64> = controlled.by_ref().take(4410).collect();
+    assert_ne!(filtered, expected[..4410]);
+    control.set_cutoff(22050.);
+    let restored: Vec<f64> = controlled.skip(4410).collect();
+    assert_eq!(restored, expected[8820..]);
+}
+
+#[test]
+fn serde_json_brown_noise_rolloff() {
+    let description: Description = serde_json::from_str(
+        r#"{"_version": 1, "_name": "brown", "sustain": 1, "amplification": 1,
+            "frequency": 44100, "waveform": "brownnoise"}"#,
+    )
+    .unwrap();
+    let samples: Vec<f64> = description.build().unwrap().collect();
+
+    // Drawn once per sample, each step is the rolloff times a uniform value in [-1, 1].
+    let mean_step =
+        samples.windows(2).map(|w| (w[1] - w[0]).abs()).sum::<f64>() / (samples.len() - 1) as f64;
+    let expected = BrownNoise::SIMPLE_ROLLOFF / 2.;
+    assert!(
+        (mean_step - expected).abs() < 0.1 * expected,
+        "{}",
+        mean_step
+    );
+}
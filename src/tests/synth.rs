@@ -0,0 +1,32 @@
+use crate::{envelope::Envelope, synth::Synth, waveform::Square};
+use std::f64::consts::{PI, TAU};
+
+#[test]
+fn oversampled_square_is_closer_to_band_limited() {
+    let sample_rate = 44100;
+    let freq = 2500.;
+    let ideal = |p: f64| {
+        4. / PI
+            * (1..=7)
+                .step_by(2)
+                .map(|k| (TAU * k as f64 * p).sin() / k as f64)
+                .sum::<f64>()
+    };
+    let rms_error = |oversampling| {
+        let waveform = Square::default_simple(freq).unwrap();
+        let envelope = Envelope::from_duration(1., 0., 0.1, 0., 0., None).unwrap();
+        let synth = Synth::new_oversampled(sample_rate, oversampling, waveform, envelope).unwrap();
+        let samples: Vec<f64> = synth.collect();
+        // skip the filter's ramp-up from silence
+        let sum: f64 = samples
+            .iter()
+            .enumerate()
+            .skip(100)
+            .map(|(i, s)| (s - ideal((freq * i as f64 / sample_rate as f64).fract())).powi(2))
+            .sum();
+        (sum / (samples.len() - 100) as f64).sqrt()
+    };
+    let naive = rms_error(1);
+    let oversampled = rms_error(8);
+    assert!(oversampled < naive * 0.5, "{} vs {}", oversampled, naive);
+}
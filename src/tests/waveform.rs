@@ -0,0 +1,117 @@
+use std::f64::consts::{PI, TAU};
+
+use super::count_cycles;
+use crate::{
+    envelope::Envelope,
+    synth::Synth,
+    traits::ProcState,
+    waveform::{Antialiasing, Arpeggio, Fm, RingMod, Sawtooth, Sine, Wavetable},
+};
+
+#[test]
+fn sine_sweep_integrates_phase() {
+    let sample_rate = 44100;
+    let freq = Envelope::from_points(vec![(0., 100.), (1., 300.)], None).unwrap();
+    let envelope = Envelope::from_duration(1., 0., 1., 0., 0., None).unwrap();
+    let samples: Vec<f64> = Synth::new(sample_rate, Sine::new(freq), envelope)
+        .unwrap()
+        .collect();
+
+    // A linear sweep from 100 Hz to 300 Hz completes 200 cycles in one second,
+    // and its second half (200 Hz to 300 Hz) completes 125 of them.
+    let cycles = count_cycles(&samples);
+    assert!((199..=201).contains(&cycles), "{} cycles", cycles);
+    let second_half = count_cycles(&samples[samples.len() / 2..]);
+    assert!((124..=126).contains(&second_half), "{} cycles", second_half);
+}
+
+#[test]
+fn poly_blep_sawtooth_is_closer_to_band_limited() {
+    let sample_rate = 44100.;
+    let freq = 3517.;
+    let harmonics = (sample_rate / 2. / freq) as usize;
+    let ideal = |p: f64| {
+        -2. / PI
+            * (1..=harmonics)
+                .map(|k| (TAU * k as f64 * p).sin() / k as f64)
+                .sum::<f64>()
+    };
+    let rms_error = |antialiasing| {
+        let mut saw = Sawtooth::new_simple(freq)
+            .unwrap()
+            .with_antialiasing(antialiasing);
+        let n = 4410;
+        let sum: f64 = (0..n)
+            .map(|i| {
+                let t = i as f64 / sample_rate;
+                (saw.next_value(t) - ideal((freq * t).fract())).powi(2)
+            })
+            .sum();
+        (sum / n as f64).sqrt()
+    };
+    let naive = rms_error(Antialiasing::None);
+    let poly_blep = rms_error(Antialiasing::PolyBlep);
+    let oversampled = rms_error(Antialiasing::Oversample(8));
+    assert!(poly_blep < naive * 0.5, "{} vs {}", poly_blep, naive);
+    assert!(oversampled < naive * 0.5, "{} vs {}", oversampled, naive);
+}
+
+#[test]
+fn arpeggio_steps_through_semitones() {
+    let sample_rate = 44100;
+    let arpeggio = Arpeggio::new(0.25, vec![0., 12., 7.])
+        .unwrap()
+        .with_repeat(true);
+    let envelope = Envelope::from_duration(1., 0., 1., 0., 0., None).unwrap();
+    let sine = Sine::new_simple(100.).unwrap().with_arpeggio(arpeggio);
+    let samples: Vec<f64> = Synth::new(sample_rate, sine, envelope).unwrap().collect();
+
+    // 100 Hz, 200 Hz, ~149.8 Hz, then 100 Hz again, a quarter second each.
+    let quarter = samples.len() / 4;
+    let cycles: Vec<usize> = samples.chunks(quarter).take(4).map(count_cycles).collect();
+    for (cycles, expected) in cycles.iter().zip([25, 50, 37, 25]) {
+        assert!(cycles.abs_diff(expected) <= 1, "{:?}", cycles);
+    }
+    assert!(Arpeggio::new(0., vec![12.]).is_none());
+    assert!(Arpeggio::new(0.1, vec![]).is_none());
+}
+
+#[test]
+fn wavetable_fm_and_ring_mod() {
+    let dt = 1. / 44100.;
+    let table = (0..256).map(|i| (TAU * i as f64 / 256.).sin()).collect();
+    let mut wavetable = Wavetable::new_simple(100., table).unwrap();
+    let mut fm = Fm::new_simple(100., 2., 0.).unwrap();
+    let mut sine = Sine::new_simple(100.).unwrap();
+    let mut ring = RingMod::new(
+        Sine::new_simple(100.).unwrap(),
+        Sine::new_simple(30.).unwrap(),
+    );
+    for i in 0..4410 {
+        let t = i as f64 * dt;
+        let expected = sine.next_value(t);
+        assert!((wavetable.next_value(t) - expected).abs() < 1e-3);
+        assert!((fm.next_value(t) - expected).abs() < 1e-9);
+        let product = (TAU * 100. * t).sin() * (TAU * 30. * t).sin();
+        assert!((ring.next_value(t) - product).abs() < 1e-6);
+    }
+    assert!(Wavetable::new_simple(100., vec![]).is_none());
+
+    // Modulating at the carrier frequency adds harmonics, raising the crossing rate.
+    let envelope = Envelope::from_duration(1., 0., 1., 0., 0., None).unwrap();
+    let fm = Fm::new_simple(100., 1., 5.).unwrap();
+    let samples: Vec<f64> = Synth::new(44100, fm, envelope).unwrap().collect();
+    assert!(count_cycles(&samples) > 150);
+}
+
+#[test]
+fn fm_repeated_time_does_not_drift() {
+    let mut once = Fm::new_simple(100., 3.5, 5.).unwrap();
+    let mut twice = Fm::new_simple(100., 3.5, 5.).unwrap();
+    for i in 0..4410 {
+        let t = i as f64 / 44100.;
+        let expected = once.next_value(t);
+        twice.next_value(t);
+        assert!((twice.next_value(t) - expected).abs() < 1e-12, "at {}", t);
+    }
+}
@@ -6,8 +6,48 @@ pub trait ProcState {
     fn next_value(&mut self, t: f64) -> f64;
 }
 
+impl<P> ProcState for P
+where
+    P: Proc,
+{
+    fn next_value(&mut self, t: f64) -> f64 {
+        self.value(t)
+    }
+}
+
 pub trait Duration {
     fn duration(&self) -> f64;
+
+    /// Fixes a requested release at time `t`, ending an open-ended duration.
+    fn latch_release(&self, _t: f64) {}
+}
+
+pub trait SampleRate {
+    fn sample_rate(&self) -> u32;
+}
+
+pub trait Synth: Iterator<Item = f64> + Duration + SampleRate + Send {}
+
+impl<S> Duration for Box<S>
+where
+    S: Duration + ?Sized,
+{
+    fn duration(&self) -> f64 {
+        (**self).duration()
+    }
+
+    fn latch_release(&self, t: f64) {
+        (**self).latch_release(t)
+    }
+}
+
+impl<S> SampleRate for Box<S>
+where
+    S: SampleRate + ?Sized,
+{
+    fn sample_rate(&self) -> u32 {
+        (**self).sample_rate()
+    }
 }
 
-pub trait Synth: Iterator<Item = f64> + Duration + Send {}
+impl<S> Synth for Box<S> where S: Synth + ?Sized {}
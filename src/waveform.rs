@@ -1,135 +1,388 @@
-use crate::{envelope::Envelope, traits::Proc};
+use crate::{
+    envelope::Envelope,
+    traits::{Proc, ProcState},
+};
 
-pub struct Sine {
+/// Stepped pitch changes: every `interval` seconds the frequency moves to the next offset
+/// of `steps`, in semitones above the base frequency.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arpeggio {
+    interval: f64,
+    steps: Vec<f64>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    repeat: bool,
+}
+
+impl Arpeggio {
+    /// Holds the last step once the pattern has played through.
+    pub fn new(interval: f64, steps: Vec<f64>) -> Option<Self> {
+        let arpeggio = Self {
+            interval,
+            steps,
+            repeat: false,
+        };
+        arpeggio.is_valid().then_some(arpeggio)
+    }
+
+    /// A single pitch change by `semitones` after `delay`, like sfxr's change amount and speed.
+    pub fn new_simple(delay: f64, semitones: f64) -> Option<Self> {
+        Self::new(delay, vec![0., semitones])
+    }
+
+    /// Starts the pattern over once it has played through.
+    pub fn with_repeat(self, repeat: bool) -> Self {
+        Self { repeat, ..self }
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.interval.is_finite()
+            && self.interval > 0.
+            && !self.steps.is_empty()
+            && self.steps.iter().all(|s| s.is_finite())
+    }
+
+    /// Frequency multiplier at `t`.
+    pub fn ratio(&self, t: f64) -> f64 {
+        let step = (t / self.interval).max(0.) as usize;
+        let step = if self.repeat {
+            step % self.steps.len()
+        } else {
+            step.min(self.steps.len() - 1)
+        };
+        (self.steps[step] / 12.).exp2()
+    }
+}
+
+pub(crate) struct Phase {
     freq: Envelope,
+    arpeggio: Option<Arpeggio>,
+    t: f64,
+    phase: f64,
+    step: f64,
+    wraps: usize,
+}
+impl Phase {
+    pub(crate) fn new(freq: Envelope) -> Self {
+        Self {
+            freq,
+            arpeggio: None,
+            t: 0.,
+            phase: 0.,
+            step: 0.,
+            wraps: 0,
+        }
+    }
+    /// Integrates the frequency up to `t` and returns the phase within the current cycle, in `[0, 1)`.
+    pub(crate) fn advance(&mut self, t: f64) -> f64 {
+        let dt = t - self.t;
+        self.wraps = 0;
+        if dt > 0. {
+            let ratio = self.arpeggio.as_ref().map_or(1., |a| a.ratio(self.t));
+            self.step = self.freq.value(self.t) * ratio * dt;
+            let phase = self.phase + self.step;
+            self.wraps = phase.max(0.) as usize;
+            self.phase = phase.fract();
+        }
+        self.t = t;
+        self.phase
+    }
+    pub(crate) fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+        Self {
+            arpeggio: Some(arpeggio),
+            ..self
+        }
+    }
+    /// Phase increment of the last [`Phase::advance`], in cycles.
+    pub(crate) fn step(&self) -> f64 {
+        self.step
+    }
+    /// Number of cycles completed by the last [`Phase::advance`].
+    pub(crate) fn wraps(&self) -> usize {
+        self.wraps
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Antialiasing {
+    #[default]
+    None,
+    PolyBlep,
+    Oversample(usize),
+}
+
+impl Antialiasing {
+    fn render(
+        &self,
+        phase: &Phase,
+        naive: impl Fn(f64) -> f64,
+        blep: impl Fn(f64, f64) -> f64,
+    ) -> f64 {
+        let p = phase.phase;
+        let dp = phase.step();
+        match *self {
+            Self::PolyBlep if dp > 0. => naive(p) + blep(p, dp),
+            Self::Oversample(n) if n > 1 => {
+                let sum: f64 = (0..n)
+                    .map(|k| naive((p + dp * ((k as f64 + 0.5) / n as f64 - 0.5)).rem_euclid(1.)))
+                    .sum();
+                sum / n as f64
+            }
+            _ => naive(p),
+        }
+    }
+}
+
+/// Signed distance from `edge` to `p` in samples, wrapped around the cycle.
+#[inline]
+fn edge_distance(p: f64, edge: f64, dp: f64) -> f64 {
+    ((p - edge + 0.5).rem_euclid(1.) - 0.5) / dp
+}
+
+/// Residual of a band-limited unit step at `edge`.
+#[inline]
+fn poly_blep(p: f64, edge: f64, dp: f64) -> f64 {
+    let t = edge_distance(p, edge, dp);
+    if (0. ..1.).contains(&t) {
+        -(1. - t) * (1. - t) / 2.
+    } else if (-1. ..0.).contains(&t) {
+        (1. + t) * (1. + t) / 2.
+    } else {
+        0.
+    }
+}
+
+/// Residual of a band-limited unit change of slope (per sample) at `edge`.
+#[inline]
+fn poly_blamp(p: f64, edge: f64, dp: f64) -> f64 {
+    let t = edge_distance(p, edge, dp);
+    if (0. ..1.).contains(&t) {
+        (1. - t).powi(3) / 6.
+    } else if (-1. ..0.).contains(&t) {
+        (1. + t).powi(3) / 6.
+    } else {
+        0.
+    }
+}
+
+pub struct Sine {
+    phase: Phase,
 }
 impl Sine {
     pub fn new(freq: Envelope) -> Self {
-        Self { freq }
+        Self {
+            phase: Phase::new(freq),
+        }
+    }
+    pub fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+        Self {
+            phase: self.phase.with_arpeggio(arpeggio),
+        }
     }
     pub fn new_simple(freq: f64) -> Option<Self> {
         if !freq.is_normal() || freq <= 0. {
             None
         } else {
             let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
-            Some(Self { freq })
+            Some(Self::new(freq))
         }
     }
 }
-impl Proc for Sine {
-    fn value(&self, t: f64) -> f64 {
-        (std::f64::consts::TAU * self.freq.value(t) * t).sin()
+impl ProcState for Sine {
+    fn next_value(&mut self, t: f64) -> f64 {
+        (std::f64::consts::TAU * self.phase.advance(t)).sin()
     }
 }
 
 pub struct Triangle {
-    freq: Envelope,
+    phase: Phase,
+    antialiasing: Antialiasing,
 }
 impl Triangle {
     pub fn new(freq: Envelope) -> Self {
-        Self { freq }
+        Self {
+            phase: Phase::new(freq),
+            antialiasing: Antialiasing::None,
+        }
+    }
+    pub fn with_antialiasing(self, antialiasing: Antialiasing) -> Self {
+        Self {
+            antialiasing,
+            ..self
+        }
+    }
+    pub fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+        Self {
+            phase: self.phase.with_arpeggio(arpeggio),
+            ..self
+        }
     }
     pub fn new_simple(freq: f64) -> Option<Self> {
         if !freq.is_normal() || freq <= 0. {
             None
         } else {
             let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
-            Some(Self { freq })
+            Some(Self::new(freq))
         }
     }
 }
-impl Proc for Triangle {
-    fn value(&self, t: f64) -> f64 {
-        let v = (self.freq.value(t) * t).fract();
-        if v < 0.25 {
-            4. * v
-        } else if v < 0.75 {
-            2. - 4. * v
-        } else {
-            -4. + 4. * v
-        }
+impl ProcState for Triangle {
+    fn next_value(&mut self, t: f64) -> f64 {
+        self.phase.advance(t);
+        self.antialiasing.render(
+            &self.phase,
+            |v| {
+                if v < 0.25 {
+                    4. * v
+                } else if v < 0.75 {
+                    2. - 4. * v
+                } else {
+                    -4. + 4. * v
+                }
+            },
+            |p, dp| 8. * dp * (poly_blamp(p, 0.75, dp) - poly_blamp(p, 0.25, dp)),
+        )
     }
 }
 
 pub struct Sawtooth {
-    freq: Envelope,
+    phase: Phase,
+    antialiasing: Antialiasing,
 }
 impl Sawtooth {
     pub fn new(freq: Envelope) -> Self {
-        Self { freq }
+        Self {
+            phase: Phase::new(freq),
+            antialiasing: Antialiasing::None,
+        }
+    }
+    pub fn with_antialiasing(self, antialiasing: Antialiasing) -> Self {
+        Self {
+            antialiasing,
+            ..self
+        }
+    }
+    pub fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+        Self {
+            phase: self.phase.with_arpeggio(arpeggio),
+            ..self
+        }
     }
     pub fn new_simple(freq: f64) -> Option<Self> {
         if !freq.is_normal() || freq <= 0. {
             None
         } else {
             let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
-            Some(Self { freq })
+            Some(Self::new(freq))
         }
     }
 }
-impl Proc for Sawtooth {
-    fn value(&self, t: f64) -> f64 {
-        (self.freq.value(t) * t).fract() * 2. - 1.
+impl ProcState for Sawtooth {
+    fn next_value(&mut self, t: f64) -> f64 {
+        self.phase.advance(t);
+        self.antialiasing.render(
+            &self.phase,
+            |v| v * 2. - 1.,
+            |p, dp| -2. * poly_blep(p, 0., dp),
+        )
     }
 }
 
 pub struct Breaker {
-    freq: Envelope,
+    phase: Phase,
 }
 impl Breaker {
     pub fn new(freq: Envelope) -> Self {
-        Self { freq }
+        Self {
+            phase: Phase::new(freq),
+        }
+    }
+    pub fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+        Self {
+            phase: self.phase.with_arpeggio(arpeggio),
+        }
     }
     pub fn new_simple(freq: f64) -> Option<Self> {
         if !freq.is_normal() || freq <= 0. {
             None
         } else {
             let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
-            Some(Self { freq })
+            Some(Self::new(freq))
         }
     }
 }
-impl Proc for Breaker {
-    fn value(&self, t: f64) -> f64 {
-        const BREAKER_OFFSET: f64 = 0.86602540378443864676372317075294; // f64::sqrt(0.75);
-        let v = (self.freq.value(t) * t + BREAKER_OFFSET).fract();
+impl ProcState for Breaker {
+    fn next_value(&mut self, t: f64) -> f64 {
+        const BREAKER_OFFSET: f64 = 0.866_025_403_784_438_6; // f64::sqrt(0.75);
+        let v = (self.phase.advance(t) + BREAKER_OFFSET).fract();
         -1. + 2. * (1. - 2. * v * v).abs()
     }
 }
 
 pub struct Tangent {
-    freq: Envelope,
+    phase: Phase,
     cutoff: f64,
 }
 impl Tangent {
     pub fn default(freq: Envelope) -> Self {
-        Self { freq, cutoff: 0.15 }
+        Self {
+            phase: Phase::new(freq),
+            cutoff: 0.15,
+        }
+    }
+    pub fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+        Self {
+            phase: self.phase.with_arpeggio(arpeggio),
+            ..self
+        }
     }
     pub fn new_simple(freq: f64, cutoff: f64) -> Option<Self> {
         if !freq.is_normal() || freq <= 0. || !cutoff.is_normal() || cutoff <= 0. {
             None
         } else {
             let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
-            Some(Self { freq, cutoff })
+            Some(Self {
+                phase: Phase::new(freq),
+                cutoff,
+            })
         }
     }
     pub fn default_simple(freq: f64) -> Option<Self> {
         Self::new_simple(freq, 0.15)
     }
 }
-impl Proc for Tangent {
-    fn value(&self, t: f64) -> f64 {
-        ((std::f64::consts::PI * self.freq.value(t) * t).tan() / self.cutoff).clamp(-1., 1.)
+impl ProcState for Tangent {
+    fn next_value(&mut self, t: f64) -> f64 {
+        ((std::f64::consts::PI * self.phase.advance(t)).tan() / self.cutoff).clamp(-1., 1.)
     }
 }
 
 pub struct Square {
-    freq: Envelope,
+    phase: Phase,
     square_duty: Envelope,
+    antialiasing: Antialiasing,
 }
 impl Square {
     pub fn new(freq: Envelope, square_duty: Envelope) -> Self {
-        Self { freq, square_duty }
+        Self {
+            phase: Phase::new(freq),
+            square_duty,
+            antialiasing: Antialiasing::None,
+        }
+    }
+    pub fn with_antialiasing(self, antialiasing: Antialiasing) -> Self {
+        Self {
+            antialiasing,
+            ..self
+        }
+    }
+    pub fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+        Self {
+            phase: self.phase.with_arpeggio(arpeggio),
+            ..self
+        }
     }
     pub fn new_simple(freq: f64, square_duty: f64) -> Option<Self> {
         if !freq.is_normal()
@@ -143,20 +396,146 @@ impl Square {
             let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
             let square_duty =
                 Envelope::from_duration(square_duty, 0., f64::INFINITY, 0., 0., None).unwrap();
-            Some(Self { freq, square_duty })
+            Some(Self::new(freq, square_duty))
         }
     }
     pub fn default_simple(freq: f64) -> Option<Self> {
         Self::new_simple(freq, 0.5)
     }
 }
-impl Proc for Square {
-    fn value(&self, t: f64) -> f64 {
-        let v = (self.freq.value(t) * t).fract();
-        if v < self.square_duty.value(t) {
-            1.
+impl ProcState for Square {
+    fn next_value(&mut self, t: f64) -> f64 {
+        self.phase.advance(t);
+        let duty = self.square_duty.value(t);
+        self.antialiasing.render(
+            &self.phase,
+            |v| if v < duty { 1. } else { -1. },
+            |p, dp| 2. * (poly_blep(p, 0., dp) - poly_blep(p, duty, dp)),
+        )
+    }
+}
+
+/// Single-cycle wavetable, linearly interpolated.
+pub struct Wavetable {
+    phase: Phase,
+    table: Vec<f64>,
+}
+impl Wavetable {
+    pub fn new(freq: Envelope, table: Vec<f64>) -> Option<Self> {
+        if table.is_empty() || table.iter().any(|v| !v.is_finite()) {
+            None
+        } else {
+            Some(Self {
+                phase: Phase::new(freq),
+                table,
+            })
+        }
+    }
+    pub fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+        Self {
+            phase: self.phase.with_arpeggio(arpeggio),
+            ..self
+        }
+    }
+    pub fn new_simple(freq: f64, table: Vec<f64>) -> Option<Self> {
+        if !freq.is_normal() || freq <= 0. {
+            None
+        } else {
+            let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
+            Self::new(freq, table)
+        }
+    }
+}
+impl ProcState for Wavetable {
+    fn next_value(&mut self, t: f64) -> f64 {
+        let len = self.table.len();
+        let position = self.phase.advance(t) * len as f64;
+        let i = position as usize % len;
+        crate::lerp(self.table[i], self.table[(i + 1) % len], position.fract())
+    }
+}
+
+/// Two-operator FM: a sine carrier phase-modulated by a sine at `ratio` times its frequency,
+/// with peak phase deviation `index` in radians.
+pub struct Fm {
+    phase: Phase,
+    modulator: f64,
+    /// Time the modulator was last advanced to.
+    t: f64,
+    ratio: Envelope,
+    index: Envelope,
+}
+impl Fm {
+    pub fn new(freq: Envelope, ratio: Envelope, index: Envelope) -> Self {
+        Self {
+            phase: Phase::new(freq),
+            modulator: 0.,
+            t: 0.,
+            ratio,
+            index,
+        }
+    }
+    pub fn with_arpeggio(self, arpeggio: Arpeggio) -> Self {
+        Self {
+            phase: self.phase.with_arpeggio(arpeggio),
+            ..self
+        }
+    }
+    pub fn new_simple(freq: f64, ratio: f64, index: f64) -> Option<Self> {
+        if !freq.is_normal()
+            || freq <= 0.
+            || !ratio.is_normal()
+            || ratio <= 0.
+            || !index.is_finite()
+            || index < 0.
+        {
+            None
         } else {
-            -1.
+            let freq = Envelope::from_duration(freq, 0., f64::INFINITY, 0., 0., None).unwrap();
+            let ratio = Envelope::from_duration(ratio, 0., f64::INFINITY, 0., 0., None).unwrap();
+            let index = Envelope::from_points(vec![(0., index), (f64::INFINITY, index)], None)?;
+            Some(Self::new(freq, ratio, index))
+        }
+    }
+}
+impl ProcState for Fm {
+    fn next_value(&mut self, t: f64) -> f64 {
+        use std::f64::consts::TAU;
+
+        let carrier = self.phase.advance(t);
+        if t > self.t {
+            self.modulator = (self.modulator + self.phase.step() * self.ratio.value(t)).fract();
+            self.t = t;
         }
+        let modulation = self.index.value(t) * (TAU * self.modulator).sin();
+        (TAU * carrier + modulation).sin()
+    }
+}
+
+/// Product of two sources.
+pub struct RingMod<A, B>
+where
+    A: ProcState,
+    B: ProcState,
+{
+    carrier: A,
+    modulator: B,
+}
+impl<A, B> RingMod<A, B>
+where
+    A: ProcState,
+    B: ProcState,
+{
+    pub fn new(carrier: A, modulator: B) -> Self {
+        Self { carrier, modulator }
+    }
+}
+impl<A, B> ProcState for RingMod<A, B>
+where
+    A: ProcState,
+    B: ProcState,
+{
+    fn next_value(&mut self, t: f64) -> f64 {
+        self.carrier.next_value(t) * self.modulator.next_value(t)
     }
 }